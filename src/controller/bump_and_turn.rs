@@ -0,0 +1,66 @@
+use geometry::Pose;
+use math::{Angle, Scalar, PI};
+use motion::Velocity;
+use sensor::SensorReadings;
+
+use super::mapper::Mapper;
+use super::{turn_towards, Controller, DebugState, SPEED, TURN_RATE};
+
+/// Drives straight until one of the bumpers is hit, then turns away from the
+/// obstacle by a fixed angle.
+pub struct BumpAndTurn {
+    pub mapper: Mapper,
+    /// Forward speed in m/s
+    pub speed: Scalar,
+    /// Turn rate in rad/s
+    pub turn_rate: Angle,
+    /// Heading (estimate) to turn to before driving on
+    turn_target: Option<Angle>,
+}
+
+impl Default for BumpAndTurn {
+    fn default() -> BumpAndTurn {
+        BumpAndTurn {
+            mapper: Mapper::default(),
+            speed: SPEED,
+            turn_rate: TURN_RATE,
+            turn_target: None,
+        }
+    }
+}
+
+impl Controller for BumpAndTurn {
+    fn cycle(&mut self, readings: &SensorReadings) -> Velocity {
+        self.mapper.update(readings);
+        let heading = self.mapper.pose_estimate.heading;
+
+        let bumper = &readings.bumper;
+        if bumper.left {
+            self.turn_target = Some(heading - PI / 4.0);
+        } else if bumper.right {
+            self.turn_target = Some(heading + PI / 4.0);
+        } else if bumper.center {
+            self.turn_target = Some(heading + PI / 2.0);
+        }
+
+        if let Some(target) = self.turn_target {
+            if let Some(velocity) = turn_towards(heading, target, self.turn_rate) {
+                return velocity;
+            }
+            self.turn_target = None;
+        }
+
+        Velocity::new(self.speed, 0.0)
+    }
+
+    fn pose_estimate(&self) -> Pose {
+        self.mapper.pose_estimate.clone()
+    }
+
+    fn debug_state(&self) -> DebugState<'_> {
+        DebugState {
+            gridmap: Some(&self.mapper.gridmap),
+            ..DebugState::default()
+        }
+    }
+}
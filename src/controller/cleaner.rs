@@ -0,0 +1,149 @@
+use std::collections::VecDeque;
+
+use geometry::{Line, Pose};
+use math::Scalar;
+use motion::Velocity;
+use sensor::odometry::DriveGeometry;
+use sensor::SensorReadings;
+
+use super::coverage::CoveragePlanner;
+use super::gridmap::{GridMap, RESOLUTION};
+use super::mcl::{MclConfig, MonteCarloLocalization};
+use super::path_follower::PathFollower;
+use super::planner::{CostMap, Path, Planner};
+use super::{BackOff, Controller, DebugState};
+
+/// Cleans the floor of a known map: covers the free space with lawnmower
+/// sweeps, and localizes with a particle filter from the known start pose.
+pub struct Cleaner {
+    pub mcl: MonteCarloLocalization,
+    pub drive_geometry: DriveGeometry,
+    pub coverage: CoveragePlanner,
+    pub planner: Planner,
+    pub follower: PathFollower,
+    /// The known map, rasterized
+    pub gridmap: GridMap,
+    /// Minimum distance between the robot center and obstacles on a path
+    pub clearance: Scalar,
+    /// Paths between sweeps keep further away from obstacles where possible
+    pub inflation_radius: Scalar,
+    /// Sweeps that have not been started yet (planned in the first cycle)
+    sweeps: Option<VecDeque<Path>>,
+    /// Sweep that starts at the end of the current path
+    next_sweep: Option<Path>,
+    back_off: BackOff,
+    finished: bool,
+}
+
+impl Cleaner {
+    /// Create a cleaner for the given map, which is given relative to the
+    /// start pose of the robot.
+    pub fn new(map: &[Line], seed: u64) -> Cleaner {
+        let start = Pose::default();
+        // The start pose is known, so there is no need for the many particles
+        // of a global localization
+        let mut config = MclConfig::default();
+        config.max_particles = config.num_particles;
+
+        let mut mcl = MonteCarloLocalization::new(map, config, seed);
+        mcl.initialize_at(&start, 0.02, 0.02);
+
+        let mut follower = PathFollower::default();
+        follower.lookahead = 0.2;
+
+        Cleaner {
+            mcl,
+            drive_geometry: DriveGeometry::default(),
+            coverage: CoveragePlanner::default(),
+            planner: Planner::default(),
+            follower,
+            gridmap: GridMap::from_walls(map, start.position, RESOLUTION),
+            clearance: 0.2,
+            inflation_radius: 0.4,
+            sweeps: None,
+            next_sweep: None,
+            back_off: BackOff::default(),
+            finished: false,
+        }
+    }
+
+    /// Start the next sweep, or plan the path to it. Returns `false` once
+    /// all sweeps are done.
+    fn next_path(&mut self) -> bool {
+        if let Some(sweep) = self.next_sweep.take() {
+            self.follower.set_path(sweep);
+            return true;
+        }
+
+        let costmap = CostMap::new(&self.gridmap, self.clearance, self.inflation_radius);
+        let position = self.pose_estimate().position;
+        let coverage = self.coverage;
+        let sweeps = self
+            .sweeps
+            .get_or_insert_with(|| coverage.plan(&costmap, position).into());
+
+        // Sweeps that can not be reached are skipped
+        while let Some(sweep) = sweeps.pop_front() {
+            if let Some(path) = self.planner.plan(&costmap, position, sweep.waypoints[0]) {
+                self.follower.set_path(path);
+                self.next_sweep = Some(sweep);
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl Controller for Cleaner {
+    fn cycle(&mut self, readings: &SensorReadings) -> Velocity {
+        let motion = self.drive_geometry.to_motion(&readings.odometry);
+        self.mcl.predict(&motion);
+
+        if let Some(ref scan) = readings.scan {
+            self.mcl.correct(scan);
+        }
+
+        if self.finished {
+            return Velocity::stop();
+        }
+
+        if let Some(velocity) = self.back_off.velocity(readings, self.follower.speed) {
+            return velocity;
+        }
+
+        if self.follower.is_done() && !self.next_path() {
+            self.finished = true;
+            return Velocity::stop();
+        }
+
+        self.follower
+            .follow(&self.pose_estimate())
+            .unwrap_or_else(Velocity::stop)
+    }
+
+    fn pose_estimate(&self) -> Pose {
+        self.mcl.estimate().0
+    }
+
+    fn debug_state(&self) -> DebugState<'_> {
+        DebugState {
+            gridmap: Some(&self.gridmap),
+            particles: Some(&self.mcl.particles),
+            covariance: Some(self.mcl.estimate().1),
+            path: Some(self.follower.path()),
+        }
+    }
+
+    fn set_map(&mut self, gridmap: GridMap) -> bool {
+        self.gridmap = gridmap;
+        self.sweeps = None;
+        self.next_sweep = None;
+        self.follower.clear();
+        true
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
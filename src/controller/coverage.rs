@@ -0,0 +1,348 @@
+//! Coverage path planning with the boustrophedon cellular decomposition
+//! (Choset, "Coverage Path Planning: The Boustrophedon Cellular
+//! Decomposition"): the free space is split into regions that can each be
+//! covered with simple back-and-forth motions.
+
+use geometry::Vector;
+use math::Scalar;
+
+use super::explorer::frontier::Reachable;
+use super::planner::{smooth, CostMap, Path};
+
+/// A run of free cells in one column of the grid, from the `bottom` to the
+/// `top` row (inclusive).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slice {
+    pub column: i64,
+    pub bottom: i64,
+    pub top: i64,
+}
+
+impl Slice {
+    fn overlaps(&self, other: &Slice) -> bool {
+        self.bottom <= other.top && other.bottom <= self.top
+    }
+}
+
+/// A region of the decomposition: slices of consecutive columns, each of
+/// which overlaps the previous one.
+#[derive(Debug, Clone, Default)]
+pub struct Region {
+    pub slices: Vec<Slice>,
+}
+
+/// Split the free cells of the cost map into regions. Sweeping over the
+/// columns from left to right, a new region starts wherever the connectivity
+/// of the free space changes, i.e. where an obstacle begins or ends.
+pub fn decompose(costmap: &CostMap<'_>) -> Vec<Region> {
+    let (min, max) = match costmap.gridmap.bounds() {
+        Some(bounds) => bounds,
+        None => return vec![],
+    };
+
+    let mut regions: Vec<Region> = vec![];
+
+    // Slices of the previous column, with the index of their region
+    let mut previous: Vec<(Slice, usize)> = vec![];
+
+    for column in min.0..=max.0 {
+        let slices = column_slices(costmap, column, min.1, max.1);
+
+        let mut current = Vec::with_capacity(slices.len());
+        for &slice in &slices {
+            let overlapping: Vec<&(Slice, usize)> = previous
+                .iter()
+                .filter(|(other, _)| other.overlaps(&slice))
+                .collect();
+
+            // A region continues as long as it neither splits nor merges
+            let region = match overlapping.as_slice() {
+                [(other, index)] if slices.iter().filter(|s| s.overlaps(other)).count() == 1 => {
+                    *index
+                }
+                _ => {
+                    regions.push(Region::default());
+                    regions.len() - 1
+                }
+            };
+
+            regions[region].slices.push(slice);
+            current.push((slice, region));
+        }
+
+        previous = current;
+    }
+
+    regions
+}
+
+/// Runs of free cells in the given column, between the given rows.
+fn column_slices(costmap: &CostMap<'_>, column: i64, bottom: i64, top: i64) -> Vec<Slice> {
+    let mut slices = vec![];
+    let mut start = None;
+
+    for row in bottom..=top + 1 {
+        let free = row <= top && costmap.is_free((column, row));
+        match (start, free) {
+            (None, true) => start = Some(row),
+            (Some(first), false) => {
+                slices.push(Slice {
+                    column,
+                    bottom: first,
+                    top: row - 1,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    slices
+}
+
+/// Plans lawnmower sweeps that cover the free space of a map. The lanes run
+/// along the y axis.
+#[derive(Debug, Clone, Copy)]
+pub struct CoveragePlanner {
+    /// Width of the strip that the robot cleans while driving
+    pub cleaning_width: Scalar,
+    /// Overlap of neighboring strips, so that small tracking errors do not
+    /// leave gaps
+    pub overlap: Scalar,
+}
+
+impl Default for CoveragePlanner {
+    fn default() -> CoveragePlanner {
+        CoveragePlanner {
+            cleaning_width: 0.3,
+            overlap: 0.05,
+        }
+    }
+}
+
+impl CoveragePlanner {
+    /// Distance between neighboring lanes.
+    pub fn lane_spacing(&self) -> Scalar {
+        self.cleaning_width - self.overlap
+    }
+
+    /// Sweeps that cover all regions of the free space. They are sorted such
+    /// that the closest remaining sweep (driven in either direction, by the
+    /// length of the path to it) follows next, starting from `start`.
+    pub fn plan(&self, costmap: &CostMap<'_>, start: Vector) -> Vec<Path> {
+        let gridmap = costmap.gridmap;
+        let mut sweeps: Vec<Path> = decompose(costmap)
+            .iter()
+            .map(|region| self.sweep(costmap, region))
+            .collect();
+
+        let mut ordered = vec![];
+        let mut position = start;
+        while !sweeps.is_empty() {
+            let reachable =
+                Reachable::new(gridmap, gridmap.cell_at(position), costmap.robot_radius);
+            let distance = |p: Vector| {
+                reachable
+                    .distance(gridmap.cell_at(p))
+                    .unwrap_or(Scalar::INFINITY)
+            };
+
+            let (index, reverse, _) = sweeps
+                .iter()
+                .enumerate()
+                .flat_map(|(i, sweep)| {
+                    let first = sweep.waypoints[0];
+                    let last = *sweep.waypoints.last().unwrap();
+                    vec![(i, false, distance(first)), (i, true, distance(last))]
+                })
+                .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
+                .unwrap();
+
+            let mut sweep = sweeps.swap_remove(index);
+            if reverse {
+                sweep.waypoints.reverse();
+            }
+            position = *sweep.waypoints.last().unwrap();
+            ordered.push(sweep);
+        }
+
+        ordered
+    }
+
+    /// A path that covers the region with evenly spaced lanes along its
+    /// columns. The first and the last column are always covered, neighboring
+    /// lanes are connected along the boundary of the region.
+    ///
+    /// Lanes are at most the lane spacing apart (rounded down to whole grid
+    /// cells), but at least one cell.
+    pub fn sweep(&self, costmap: &CostMap<'_>, region: &Region) -> Path {
+        let gridmap = costmap.gridmap;
+        let slices = &region.slices;
+        // In whole columns, so that no gap gets wider than the lane spacing.
+        // The tolerance keeps spacings like 0.3 - 0.05 at the full number of
+        // cells despite rounding errors.
+        let spacing = (self.lane_spacing() / gridmap.resolution() + 1e-6)
+            .floor()
+            .max(1.0);
+        let num_gaps = ((slices.len() - 1) as Scalar / spacing).ceil() as usize;
+        let lanes: Vec<usize> = if num_gaps == 0 {
+            vec![0]
+        } else {
+            (0..=num_gaps)
+                .map(|k| k * (slices.len() - 1) / num_gaps)
+                .collect()
+        };
+
+        let point = |i: usize, row: i64| gridmap.cell_center((slices[i].column, row));
+
+        let mut waypoints = vec![];
+        for (k, &lane) in lanes.iter().enumerate() {
+            // Alternate between upwards and downwards lanes
+            let upwards = k % 2 == 0;
+
+            if k > 0 {
+                // Follow the top (or bottom) boundary from the end of the
+                // previous lane, as a staircase through free cells
+                let mut connection = vec![*waypoints.last().unwrap()];
+                for i in lanes[k - 1] + 1..=lane {
+                    let row = if upwards {
+                        slices[i - 1].bottom.max(slices[i].bottom)
+                    } else {
+                        slices[i - 1].top.min(slices[i].top)
+                    };
+                    connection.push(point(i - 1, row));
+                    connection.push(point(i, row));
+                }
+                connection.push(point(
+                    lane,
+                    if upwards {
+                        slices[lane].bottom
+                    } else {
+                        slices[lane].top
+                    },
+                ));
+                connection.dedup();
+
+                waypoints.extend(smooth(costmap, &connection).into_iter().skip(1));
+            }
+
+            let (from, to) = if upwards {
+                (slices[lane].bottom, slices[lane].top)
+            } else {
+                (slices[lane].top, slices[lane].bottom)
+            };
+            waypoints.push(point(lane, from));
+            waypoints.push(point(lane, to));
+        }
+
+        waypoints.dedup();
+        Path::new(waypoints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use controller::gridmap::{walls, GridMap, RESOLUTION};
+
+    /// A room of 4 m x 3 m with a square obstacle of 1 m x 1 m in the middle.
+    fn room_with_obstacle() -> GridMap {
+        let mut lines = walls(&[(0.0, 0.0), (4.0, 0.0), (4.0, 3.0), (0.0, 3.0)]);
+        lines.extend(walls(&[(1.5, 1.0), (2.5, 1.0), (2.5, 2.0), (1.5, 2.0)]));
+        GridMap::from_walls(&lines, Vector::new(0.5, 0.5), RESOLUTION)
+    }
+
+    #[test]
+    fn regions_around_obstacle() {
+        let gridmap = room_with_obstacle();
+        let costmap = CostMap::new(&gridmap, 0.2, 0.2);
+
+        // Corners of the regions: bottom left and top right
+        let corners: Vec<(Vector, Vector)> = decompose(&costmap)
+            .iter()
+            .map(|region| {
+                let first = region.slices[0];
+                let last = *region.slices.last().unwrap();
+                (
+                    gridmap.cell_center((first.column, first.bottom)),
+                    gridmap.cell_center((last.column, last.top)),
+                )
+            })
+            .collect();
+
+        // Left of, below, above and right of the obstacle
+        assert_eq!(corners.len(), 4);
+        assert!(corners[0].1.x < 1.5);
+        assert!(corners[1].1.y < 1.0);
+        assert!(corners[2].0.y > 2.0);
+        assert!(corners[3].0.x > 2.5);
+    }
+
+    /// Columns of the lanes of a sweep, from left to right.
+    fn lanes(sweep: &Path) -> Vec<Scalar> {
+        let mut columns: Vec<Scalar> = sweep
+            .waypoints
+            .windows(2)
+            .filter(|pair| pair[0].x == pair[1].x)
+            .map(|pair| pair[0].x)
+            .collect();
+        columns.dedup();
+        columns
+    }
+
+    /// The first and the last column of each region are lanes, and the lanes
+    /// in between are at most the lane spacing apart.
+    #[test]
+    fn lanes_cover_regions() {
+        let gridmap = room_with_obstacle();
+        let costmap = CostMap::new(&gridmap, 0.2, 0.2);
+
+        // Spacings of a whole number of cells, and between them
+        let planners = [
+            CoveragePlanner::default(),
+            CoveragePlanner {
+                cleaning_width: 0.3,
+                overlap: 0.025,
+            },
+            CoveragePlanner {
+                cleaning_width: 0.33,
+                overlap: 0.0,
+            },
+        ];
+
+        for planner in &planners {
+            for region in decompose(&costmap) {
+                let columns = lanes(&planner.sweep(&costmap, &region));
+                let first = gridmap.cell_center((region.slices[0].column, 0)).x;
+                let last = gridmap
+                    .cell_center((region.slices.last().unwrap().column, 0))
+                    .x;
+
+                assert_eq!(columns.first(), Some(&first));
+                assert_eq!(columns.last(), Some(&last));
+                for pair in columns.windows(2) {
+                    let gap = pair[1] - pair[0];
+                    assert!(gap > 0.0);
+                    assert!(gap <= planner.lane_spacing() + 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn overlap_as_wide_as_cleaning_width() {
+        let gridmap = room_with_obstacle();
+        let costmap = CostMap::new(&gridmap, 0.2, 0.2);
+        let planner = CoveragePlanner {
+            cleaning_width: 0.3,
+            overlap: 0.3,
+        };
+
+        // One lane per column
+        for region in decompose(&costmap) {
+            let columns = lanes(&planner.sweep(&costmap, &region));
+            assert_eq!(columns.len(), region.slices.len());
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Extended Kalman filter for the pose of the robot, see chapter 7.4 of
+//! "Probabilistic Robotics" by Thrun, Burgard and Fox.
+
+use geometry::{Pose, Vector};
+use math::{normalize_angle, Matrix3, Scalar};
+use motion::{Motion, MotionNoise, Velocity};
+use scanmatch::{IcpConfig, ScanToScan};
+use sensor::odometry::DriveGeometry;
+use sensor::SensorReadings;
+
+use super::wall_follower::WallSteering;
+use super::{Controller, DebugState};
+
+/// Gaussian estimate of the pose, with the covariance of (x, y, heading).
+pub struct Ekf {
+    pub pose: Pose,
+    pub covariance: Matrix3,
+    /// Noise of the odometry motion model
+    pub motion_noise: MotionNoise,
+}
+
+impl Ekf {
+    pub fn new(pose: Pose, covariance: Matrix3, motion_noise: MotionNoise) -> Ekf {
+        Ekf {
+            pose,
+            covariance,
+            motion_noise,
+        }
+    }
+
+    /// Propagate the estimate with the odometry motion model.
+    pub fn predict(&mut self, motion: &Motion) {
+        let heading = self.pose.heading + motion.rotation1;
+        let (s, c) = heading.sin_cos();
+        let t = motion.translation;
+
+        // Jacobian of the motion model with respect to the state ...
+        let g = Matrix3::new([[1.0, 0.0, -t * c], [0.0, 1.0, -t * s], [0.0, 0.0, 1.0]]);
+
+        // ... and with respect to the motion (rotation1, translation, rotation2)
+        let v = Matrix3::new([[-t * c, -s, 0.0], [-t * s, c, 0.0], [1.0, 0.0, 1.0]]);
+
+        let noise = &self.motion_noise;
+        let (rot1, trans, rot2) = (
+            motion.rotation1.powi(2),
+            motion.translation.powi(2),
+            motion.rotation2.powi(2),
+        );
+        let m = Matrix3::diagonal([
+            noise.alpha1 * rot1 + noise.alpha2 * trans,
+            noise.alpha3 * trans + noise.alpha4 * (rot1 + rot2),
+            noise.alpha1 * rot2 + noise.alpha2 * trans,
+        ]);
+
+        self.pose = motion.apply(&self.pose);
+        self.covariance = g * self.covariance * g.transpose() + v * m * v.transpose();
+    }
+
+    /// Fuse a direct measurement of the pose with the given covariance.
+    /// Returns `false` (and ignores the measurement) if the covariance of the
+    /// innovation is not positive definite.
+    #[must_use]
+    pub fn correct(&mut self, measurement: &Pose, noise: &Matrix3) -> bool {
+        let innovation = [
+            measurement.position.x - self.pose.position.x,
+            measurement.position.y - self.pose.position.y,
+            normalize_angle(measurement.heading - self.pose.heading),
+        ];
+
+        let gain = match (self.covariance + *noise).inverse_spd() {
+            Some(inverse) => self.covariance * inverse,
+            None => return false,
+        };
+
+        let update = gain.mul_vector(innovation);
+        self.pose = Pose::new(
+            self.pose.position + Vector::new(update[0], update[1]),
+            self.pose.heading + update[2],
+        );
+        self.covariance = (Matrix3::identity() - gain) * self.covariance;
+        true
+    }
+}
+
+/// Concatenate two independent uncertain poses, where `b` is given relative
+/// to `a`. Returns the resulting pose and its covariance.
+pub fn compose(
+    a: &Pose,
+    a_covariance: &Matrix3,
+    b: &Pose,
+    b_covariance: &Matrix3,
+) -> (Pose, Matrix3) {
+    let offset = b.position.rotate(a.heading);
+    let (s, c) = a.heading.sin_cos();
+
+    // Jacobians of the composition with respect to a and b
+    let ja = Matrix3::new([[1.0, 0.0, -offset.y], [0.0, 1.0, offset.x], [0.0, 0.0, 1.0]]);
+    let jb = Matrix3::new([[c, -s, 0.0], [s, c, 0.0], [0.0, 0.0, 1.0]]);
+
+    let covariance = ja * *a_covariance * ja.transpose() + jb * *b_covariance * jb.transpose();
+    (a.compose(b), covariance)
+}
+
+/// Follows the walls, and estimates the pose with an EKF that fuses wheel
+/// odometry with scan matching.
+///
+/// Scan matching measures the motion since the previous scan. The filter
+/// therefore only estimates this motion, relative to the pose of the
+/// previous scan (the reference). This keeps the measurements independent
+/// of the state, and the uncertainty of the reference carries over to the
+/// pose estimate.
+pub struct EkfLocalizer {
+    /// Motion since the reference scan
+    pub ekf: Ekf,
+    pub reference: Pose,
+    pub reference_covariance: Matrix3,
+    pub drive_geometry: DriveGeometry,
+    pub scan_matching: ScanToScan,
+    /// Standard deviation of scan matching results, for a match without
+    /// residual error (in meters and radians)
+    pub position_std_dev: Scalar,
+    pub heading_std_dev: Scalar,
+    /// Number of scan matches that could not be fused
+    pub rejected_matches: usize,
+    driver: WallSteering,
+}
+
+impl Default for EkfLocalizer {
+    fn default() -> EkfLocalizer {
+        EkfLocalizer {
+            // The odometry is less reliable than the nominal noise suggests,
+            // as it is affected by systematic errors (e.g. of the wheel radii)
+            ekf: Ekf::new(
+                Pose::default(),
+                Matrix3::zero(),
+                MotionNoise {
+                    alpha1: 0.1,
+                    alpha2: 0.05,
+                    alpha3: 0.1,
+                    alpha4: 0.05,
+                },
+            ),
+            reference: Pose::default(),
+            reference_covariance: Matrix3::zero(),
+            drive_geometry: DriveGeometry::default(),
+            scan_matching: ScanToScan::new(IcpConfig::default(), 0.7),
+            position_std_dev: 0.005,
+            heading_std_dev: 0.002,
+            rejected_matches: 0,
+            driver: WallSteering::default(),
+        }
+    }
+}
+
+impl EkfLocalizer {
+    /// Pose estimate, with the covariance of (x, y, heading).
+    pub fn estimate(&self) -> (Pose, Matrix3) {
+        compose(
+            &self.reference,
+            &self.reference_covariance,
+            &self.ekf.pose,
+            &self.ekf.covariance,
+        )
+    }
+}
+
+impl Controller for EkfLocalizer {
+    fn cycle(&mut self, readings: &SensorReadings) -> Velocity {
+        let motion = self.drive_geometry.to_motion(&readings.odometry);
+        self.ekf.predict(&motion);
+
+        if let Some(ref scan) = readings.scan {
+            let cloud = scan.to_pointcloud(&Pose::default());
+
+            if let Some((motion, result)) = self.scan_matching.measure(&cloud, &self.ekf.pose) {
+                // Trust the match less if the scans do not fit well
+                let scale = result.uncertainty_scale();
+                let noise = Matrix3::diagonal([
+                    (scale * self.position_std_dev).powi(2),
+                    (scale * self.position_std_dev).powi(2),
+                    (scale * self.heading_std_dev).powi(2),
+                ]);
+                if !self.ekf.correct(&motion, &noise) {
+                    self.rejected_matches += 1;
+                }
+            }
+
+            // This scan becomes the new reference
+            let (pose, covariance) = self.estimate();
+            self.reference = pose;
+            self.reference_covariance = covariance;
+            self.ekf.pose = Pose::default();
+            self.ekf.covariance = Matrix3::zero();
+            self.scan_matching.set_reference(&cloud, Pose::default());
+        }
+
+        self.driver.steer(readings)
+    }
+
+    fn pose_estimate(&self) -> Pose {
+        self.estimate().0
+    }
+
+    fn debug_state(&self) -> DebugState<'_> {
+        DebugState {
+            covariance: Some(self.estimate().1),
+            ..DebugState::default()
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Frontiers are the boundaries between known free space and unexplored
+//! space of a grid map, see "A Frontier-Based Approach for Autonomous
+//! Exploration" by Yamauchi.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use math::Scalar;
+
+use controller::gridmap::traversal::Cell;
+use controller::gridmap::{CellState, GridMap};
+use controller::planner::NEIGHBORS;
+
+/// A connected group of frontier cells.
+#[derive(Debug, Clone)]
+pub struct Frontier {
+    pub cells: Vec<Cell>,
+    /// The cell of the frontier that is closest to the start
+    pub target: Cell,
+    /// Length of the path from the start to the target, in meters
+    pub distance: Scalar,
+}
+
+/// All cells that can be reached from a start cell without getting closer
+/// to an obstacle than a given clearance, as a tree of shortest paths.
+pub struct Reachable {
+    /// Predecessor of each reachable cell on the path from the start
+    parents: HashMap<Cell, Cell>,
+    /// Length of the path to each reachable cell, in meters
+    distances: HashMap<Cell, Scalar>,
+}
+
+impl Reachable {
+    /// Breadth-first search from `start` through free cells. Cells that are
+    /// closer than `clearance` to an occupied cell are avoided, unless they
+    /// are that close to the start (so that the robot can leave a wall).
+    pub fn new(gridmap: &GridMap, start: Cell, clearance: Scalar) -> Reachable {
+        let radius = (clearance / gridmap.resolution()).ceil() as i64;
+        let blocked = inflate(gridmap, radius);
+
+        let mut parents = HashMap::new();
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        distances.insert(start, 0.0);
+        queue.push_back(start);
+
+        while let Some(cell) = queue.pop_front() {
+            let distance = distances[&cell];
+
+            for &(dx, dy) in &NEIGHBORS {
+                let next = (cell.0 + dx, cell.1 + dy);
+                if distances.contains_key(&next) || gridmap.cell_state(next) != CellState::Freespace
+                {
+                    continue;
+                }
+
+                let near_start = (next.0 - start.0).abs().max((next.1 - start.1).abs()) <= radius;
+                if blocked.contains(&next) && !near_start {
+                    continue;
+                }
+
+                let step = if dx == 0 || dy == 0 {
+                    1.0
+                } else {
+                    2.0_f64.sqrt()
+                };
+                distances.insert(next, distance + step * gridmap.resolution());
+                parents.insert(next, cell);
+                queue.push_back(next);
+            }
+        }
+
+        Reachable { parents, distances }
+    }
+
+    pub fn contains(&self, cell: Cell) -> bool {
+        self.distances.contains_key(&cell)
+    }
+
+    /// Length of the path from the start to the given cell, in meters.
+    pub fn distance(&self, cell: Cell) -> Option<Scalar> {
+        self.distances.get(&cell).cloned()
+    }
+
+    /// Cells on the path from the start to `target` (both included), or
+    /// `None` if the target can not be reached.
+    pub fn path_to(&self, target: Cell) -> Option<Vec<Cell>> {
+        if !self.contains(target) {
+            return None;
+        }
+
+        let mut path = vec![target];
+        let mut cell = target;
+        while let Some(&parent) = self.parents.get(&cell) {
+            path.push(parent);
+            cell = parent;
+        }
+        path.reverse();
+
+        Some(path)
+    }
+
+    /// Reachable cells that border on unexplored space, grouped by
+    /// connectivity. Frontiers with fewer than `min_size` cells are ignored.
+    /// The result is sorted by the distance to the start.
+    pub fn frontiers(&self, gridmap: &GridMap, min_size: usize) -> Vec<Frontier> {
+        let is_frontier = |cell: Cell| {
+            NEIGHBORS[..4]
+                .iter()
+                .any(|&(dx, dy)| gridmap.cell_state((cell.0 + dx, cell.1 + dy)) == CellState::Void)
+        };
+
+        let mut remaining: HashSet<Cell> = self
+            .distances
+            .keys()
+            .cloned()
+            .filter(|&cell| is_frontier(cell))
+            .collect();
+
+        let mut frontiers = vec![];
+        while let Some(&seed) = remaining.iter().next() {
+            remaining.remove(&seed);
+
+            let mut cells = vec![];
+            let mut stack = vec![seed];
+            while let Some(cell) = stack.pop() {
+                cells.push(cell);
+                for &(dx, dy) in &NEIGHBORS {
+                    let next = (cell.0 + dx, cell.1 + dy);
+                    if remaining.remove(&next) {
+                        stack.push(next);
+                    }
+                }
+            }
+
+            if cells.len() < min_size {
+                continue;
+            }
+
+            let (target, distance) = cells
+                .iter()
+                .map(|&cell| (cell, self.distances[&cell]))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .unwrap();
+
+            frontiers.push(Frontier {
+                cells,
+                target,
+                distance,
+            });
+        }
+
+        frontiers.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
+        frontiers
+    }
+}
+
+/// All cells within `radius` cells of an occupied cell.
+fn inflate(gridmap: &GridMap, radius: i64) -> HashSet<Cell> {
+    let mut blocked = HashSet::new();
+    for (cell, log_odds) in gridmap.cells() {
+        if gridmap.classify(log_odds) != CellState::Occupied {
+            continue;
+        }
+
+        for dx in -radius..=radius {
+            for dy in -radius..=radius {
+                if dx * dx + dy * dy <= radius * radius {
+                    blocked.insert((cell.0 + dx, cell.1 + dy));
+                }
+            }
+        }
+    }
+    blocked
+}
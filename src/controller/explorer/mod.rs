@@ -0,0 +1,230 @@
+pub mod frontier;
+
+use geometry::{Pose, Vector};
+use math::{normalize_angle, Angle, Scalar};
+use motion::Velocity;
+use sensor::SensorReadings;
+
+use super::path_follower::PathFollower;
+use super::planner::{CostMap, Planner};
+use super::slam::GraphSlam;
+use super::{BackOff, Controller, DebugState, SPEED, TURN_RATE};
+
+use self::frontier::Reachable;
+
+/// Path planning and tracking for the explorer, as an alternative to driving
+/// along the cells of the shortest path: paths are planned with A* on a cost
+/// map, which keeps them further away from obstacles, and followed with pure
+/// pursuit.
+pub struct Navigation {
+    pub planner: Planner,
+    pub follower: PathFollower,
+    /// Paths keep further away from obstacles where possible
+    pub inflation_radius: Scalar,
+}
+
+impl Default for Navigation {
+    fn default() -> Navigation {
+        Navigation {
+            planner: Planner::default(),
+            follower: PathFollower::default(),
+            inflation_radius: 0.6,
+        }
+    }
+}
+
+/// Explores an unknown environment autonomously: drives to the closest
+/// frontier of the map (built with graph-based SLAM) until no reachable
+/// frontiers remain.
+pub struct Explorer {
+    pub slam: GraphSlam,
+    /// Forward speed in m/s
+    pub speed: Scalar,
+    /// Turn rate in rad/s
+    pub turn_rate: Angle,
+    /// Minimum distance between the robot center and obstacles on a path
+    pub clearance: Scalar,
+    /// Frontiers with fewer cells are ignored
+    pub min_frontier_size: usize,
+    /// Time between two plans, in seconds
+    pub replan_interval: Scalar,
+    /// Distance to the point on the path that the robot steers towards
+    pub lookahead: Scalar,
+    /// Plan and follow paths with A* and pure pursuit, if set
+    pub navigation: Option<Navigation>,
+    /// Current path to a frontier, in the frame of the SLAM
+    path: Vec<Vector>,
+    /// Time of the last plan
+    last_plan: Option<Scalar>,
+    /// Targets that have been reached without the frontier disappearing
+    /// (e.g. due to glancing laser beams). They are not visited again.
+    visited: Vec<Vector>,
+    back_off: BackOff,
+    finished: bool,
+}
+
+impl Default for Explorer {
+    fn default() -> Explorer {
+        Explorer {
+            slam: GraphSlam::default(),
+            speed: SPEED,
+            turn_rate: TURN_RATE,
+            clearance: 0.3,
+            min_frontier_size: 8,
+            replan_interval: 2.0,
+            lookahead: 0.3,
+            navigation: None,
+            path: vec![],
+            last_plan: None,
+            visited: vec![],
+            back_off: BackOff::default(),
+            finished: false,
+        }
+    }
+}
+
+impl Explorer {
+    /// Plan a path to the closest frontier. Returns `false` if there is none.
+    fn plan(&mut self) -> bool {
+        let gridmap = &self.slam.gridmap;
+        let position = self.slam.pose_estimate().position;
+        let start = gridmap.cell_at(position);
+
+        let reachable = Reachable::new(gridmap, start, self.clearance);
+        let visited = &self.visited;
+        let lookahead = self.lookahead;
+        let mut targets = reachable
+            .frontiers(gridmap, self.min_frontier_size)
+            .into_iter()
+            .map(|frontier| frontier.target)
+            .filter(|&cell| {
+                let center = gridmap.cell_center(cell);
+                visited
+                    .iter()
+                    .all(|&v| (v - center).length() > 2.0 * lookahead)
+            });
+
+        self.path = match self.navigation {
+            None => targets
+                .next()
+                .and_then(|cell| reachable.path_to(cell))
+                .map(|cells| cells.into_iter().map(|c| gridmap.cell_center(c)).collect())
+                .unwrap_or_default(),
+            Some(ref mut navigation) => {
+                // Frontiers that the planner can not reach are skipped
+                let costmap = CostMap::new(gridmap, self.clearance, navigation.inflation_radius);
+                let planner = &navigation.planner;
+                let path = targets
+                    .filter_map(|cell| planner.plan(&costmap, position, gridmap.cell_center(cell)))
+                    .next();
+
+                match path {
+                    Some(path) => {
+                        let waypoints = path.waypoints.clone();
+                        navigation.follower.set_path(path);
+                        waypoints
+                    }
+                    None => vec![],
+                }
+            }
+        };
+
+        !self.path.is_empty()
+    }
+
+    /// Steer along the path, or return `None` once its end has been reached.
+    fn follow_path(&mut self, pose: &Pose) -> Option<Velocity> {
+        if let Some(ref mut navigation) = self.navigation {
+            return navigation.follower.follow(pose);
+        }
+
+        // Drop the part of the path that is already behind the robot
+        let lookahead = self.lookahead;
+        let ahead = self
+            .path
+            .iter()
+            .position(|&p| (p - pose.position).length() > lookahead);
+
+        let target = match ahead {
+            Some(i) => {
+                self.path.drain(..i);
+                self.path[0]
+            }
+            None => {
+                let end = *self.path.last()?;
+                if (end - pose.position).length() < lookahead / 2.0 {
+                    return None;
+                }
+                end
+            }
+        };
+
+        let error = normalize_angle((target - pose.position).angle() - pose.heading);
+        if error.abs() > 0.6 {
+            Some(Velocity::new(0.0, self.turn_rate.copysign(error)))
+        } else {
+            Some(Velocity::new(self.speed, 2.0 * error))
+        }
+    }
+}
+
+impl Controller for Explorer {
+    fn cycle(&mut self, readings: &SensorReadings) -> Velocity {
+        self.slam.update(readings);
+
+        if self.finished {
+            return Velocity::stop();
+        }
+
+        if readings.bumper.any() {
+            self.path.clear();
+        }
+        if let Some(velocity) = self.back_off.velocity(readings, self.speed) {
+            return velocity;
+        }
+
+        let replan = match self.last_plan {
+            Some(t) => readings.time - t >= self.replan_interval,
+            None => true,
+        };
+        if self.path.is_empty() || replan {
+            self.last_plan = Some(readings.time);
+            if !self.plan() {
+                self.finished = true;
+                return Velocity::stop();
+            }
+        }
+
+        let pose = self.slam.pose_estimate();
+        match self.follow_path(&pose) {
+            Some(velocity) => velocity,
+            None => {
+                // Target reached
+                if let Some(&end) = self.path.last() {
+                    self.visited.push(end);
+                }
+                self.path.clear();
+                Velocity::stop()
+            }
+        }
+    }
+
+    fn pose_estimate(&self) -> Pose {
+        self.slam.pose_estimate()
+    }
+
+    fn debug_state(&self) -> DebugState<'_> {
+        DebugState {
+            gridmap: Some(&self.slam.gridmap),
+            path: self
+                .navigation
+                .as_ref()
+                .map(|navigation| navigation.follower.path()),
+            ..DebugState::default()
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
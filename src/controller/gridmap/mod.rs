@@ -0,0 +1,295 @@
+pub mod pgm;
+pub mod traversal;
+
+use std::collections::{HashMap, HashSet};
+
+use geometry::{Line, Pose, Vector};
+use math::Scalar;
+use sensor::laserscanner::Scan;
+
+use self::traversal::{Cell, Traversal};
+
+/// Default edge length of a cell, in meters.
+pub const RESOLUTION: Scalar = 0.05;
+
+/// Number of cells along each edge of a chunk.
+pub const CHUNK_SIZE: i64 = 32;
+
+/// A square tile of `CHUNK_SIZE x CHUNK_SIZE` cells, stored row by row.
+type Chunk = Vec<Scalar>;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CellState {
+    Occupied,
+    Freespace,
+    Void,
+}
+
+/// Parameters of the inverse sensor model of the occupancy grid.
+#[derive(Debug, Clone, Copy)]
+pub struct OccupancyConfig {
+    /// Probability that a cell is occupied if a beam ends in it
+    pub p_hit: Scalar,
+    /// Probability that a cell is occupied if a beam passes through it
+    pub p_miss: Scalar,
+    /// Lower bound for the log-odds of a cell
+    pub min_log_odds: Scalar,
+    /// Upper bound for the log-odds of a cell. Together with the lower
+    /// bound, this keeps the map responsive to changes in the environment.
+    pub max_log_odds: Scalar,
+    /// Cells with a higher occupancy probability are considered occupied
+    pub occupied_threshold: Scalar,
+    /// Cells with a lower occupancy probability are considered free
+    pub free_threshold: Scalar,
+}
+
+impl Default for OccupancyConfig {
+    fn default() -> OccupancyConfig {
+        OccupancyConfig {
+            p_hit: 0.7,
+            p_miss: 0.4,
+            min_log_odds: -2.0,
+            max_log_odds: 3.5,
+            occupied_threshold: 0.65,
+            free_threshold: 0.35,
+        }
+    }
+}
+
+/// Convert a probability to log-odds.
+pub fn log_odds(probability: Scalar) -> Scalar {
+    (probability / (1.0 - probability)).ln()
+}
+
+/// Convert log-odds to a probability.
+pub fn probability(log_odds: Scalar) -> Scalar {
+    1.0 - 1.0 / (1.0 + log_odds.exp())
+}
+
+/// A closed polygon of walls through the given corners, e.g. for rasterizing
+/// test maps with `GridMap::from_walls`.
+#[cfg(test)]
+pub fn walls(corners: &[(Scalar, Scalar)]) -> Vec<Line> {
+    corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .map(|(a, b)| Line::new(Vector::new(a.0, a.1), Vector::new(b.0, b.1)))
+        .collect()
+}
+
+/// Occupancy grid map that stores the log-odds of each cell being occupied.
+/// Cells that have never been observed have log-odds zero (probability 0.5).
+///
+/// The map is made up of chunks that are allocated as soon as a measurement
+/// falls into them, so it grows with the explored area. Cells are indexed as
+/// (column, row), where the cell (0, 0) has its lower left corner at the
+/// origin.
+pub struct GridMap {
+    pub config: OccupancyConfig,
+    /// Edge length of a cell, in meters
+    resolution: Scalar,
+    chunks: HashMap<Cell, Chunk>,
+}
+
+impl Default for GridMap {
+    fn default() -> GridMap {
+        GridMap::new(RESOLUTION, OccupancyConfig::default())
+    }
+}
+
+impl GridMap {
+    pub fn new(resolution: Scalar, config: OccupancyConfig) -> GridMap {
+        GridMap {
+            config,
+            resolution,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Rasterize a known map: cells on a wall are occupied, and all cells
+    /// that can be reached from `start` without crossing a wall are free.
+    pub fn from_walls(walls: &[Line], start: Vector, resolution: Scalar) -> GridMap {
+        let mut gridmap = GridMap::new(resolution, OccupancyConfig::default());
+        let (min_log_odds, max_log_odds) =
+            (gridmap.config.min_log_odds, gridmap.config.max_log_odds);
+
+        let mut occupied = HashSet::new();
+        for wall in walls {
+            let cells = Traversal::new(
+                gridmap.grid_coords(wall.start),
+                gridmap.grid_coords(wall.end),
+            );
+            occupied.extend(cells);
+        }
+        for &cell in &occupied {
+            gridmap.set_log_odds(cell, max_log_odds);
+        }
+
+        // Flood fill, limited to the bounding box of the walls in case the
+        // start is not enclosed by them
+        let first = match occupied.iter().next() {
+            Some(&cell) => cell,
+            None => return gridmap,
+        };
+        let (min, max) = occupied.iter().fold((first, first), |(min, max), c| {
+            (
+                (min.0.min(c.0), min.1.min(c.1)),
+                (max.0.max(c.0), max.1.max(c.1)),
+            )
+        });
+
+        let mut stack = vec![gridmap.cell_at(start)];
+        let mut visited: HashSet<Cell> = stack.iter().cloned().collect();
+        while let Some(cell) = stack.pop() {
+            if occupied.contains(&cell) {
+                continue;
+            }
+            gridmap.set_log_odds(cell, min_log_odds);
+
+            for &(dx, dy) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let next = (cell.0 + dx, cell.1 + dy);
+                let inside =
+                    next.0 >= min.0 && next.0 <= max.0 && next.1 >= min.1 && next.1 <= max.1;
+                if inside && visited.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+
+        gridmap
+    }
+
+    pub fn resolution(&self) -> Scalar {
+        self.resolution
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    pub fn update(&mut self, pose: &Pose, scan: &Scan) {
+        let hit = log_odds(self.config.p_hit);
+        let miss = log_odds(self.config.p_miss);
+
+        let start = self.grid_coords(pose.position);
+
+        for &m in scan.iter() {
+            let end = self.grid_coords(m.to_vector(pose));
+            let cells = Traversal::new(start, end);
+            let num_cells = cells.len();
+
+            for (i, cell) in cells.enumerate() {
+                // The beam ends in the last cell, unless it did not hit anything
+                let delta = if m.hit && i + 1 == num_cells {
+                    hit
+                } else {
+                    miss
+                };
+
+                self.add_log_odds(cell, delta);
+            }
+        }
+    }
+
+    fn add_log_odds(&mut self, cell: Cell, delta: Scalar) {
+        let value = self.log_odds(cell) + delta;
+        self.set_log_odds(cell, value);
+    }
+
+    /// Overwrite the log-odds of a cell (clamped to the configured bounds).
+    pub fn set_log_odds(&mut self, cell: Cell, value: Scalar) {
+        let value = value.clamp(self.config.min_log_odds, self.config.max_log_odds);
+        let (chunk, index) = GridMap::split(cell);
+
+        self.chunks
+            .entry(chunk)
+            .or_insert_with(|| vec![0.0; (CHUNK_SIZE * CHUNK_SIZE) as usize])[index] = value;
+    }
+
+    /// Chunk that contains the given cell, and the index of the cell within
+    /// the chunk.
+    fn split(cell: Cell) -> (Cell, usize) {
+        let chunk = (cell.0.div_euclid(CHUNK_SIZE), cell.1.div_euclid(CHUNK_SIZE));
+        let (c, r) = (cell.0.rem_euclid(CHUNK_SIZE), cell.1.rem_euclid(CHUNK_SIZE));
+
+        (chunk, (r * CHUNK_SIZE + c) as usize)
+    }
+
+    /// Continuous grid coordinates of a position, in which the cell (c, r)
+    /// covers the square `[c, c + 1) x [r, r + 1)`.
+    fn grid_coords(&self, pos: Vector) -> Vector {
+        pos * (1.0 / self.resolution)
+    }
+
+    /// The cell that contains the given position.
+    pub fn cell_at(&self, pos: Vector) -> Cell {
+        traversal::cell_at(self.grid_coords(pos))
+    }
+
+    /// Position of the center of the given cell.
+    pub fn cell_center(&self, cell: Cell) -> Vector {
+        Vector::new(
+            (cell.0 as Scalar + 0.5) * self.resolution,
+            (cell.1 as Scalar + 0.5) * self.resolution,
+        )
+    }
+
+    /// Smallest and largest cell index (column, row) of the explored area.
+    pub fn bounds(&self) -> Option<(Cell, Cell)> {
+        let mut keys = self.chunks.keys();
+        let first = *keys.next()?;
+        let (min, max) = keys.fold((first, first), |(min, max), k| {
+            (
+                (min.0.min(k.0), min.1.min(k.1)),
+                (max.0.max(k.0), max.1.max(k.1)),
+            )
+        });
+
+        Some((
+            (min.0 * CHUNK_SIZE, min.1 * CHUNK_SIZE),
+            ((max.0 + 1) * CHUNK_SIZE - 1, (max.1 + 1) * CHUNK_SIZE - 1),
+        ))
+    }
+
+    /// All cells of the explored area, with their log-odds.
+    pub fn cells(&self) -> impl Iterator<Item = (Cell, Scalar)> + '_ {
+        self.chunks.iter().flat_map(|(&(cx, cy), chunk)| {
+            chunk.iter().enumerate().map(move |(i, &value)| {
+                let i = i as i64;
+                let cell = (
+                    cx * CHUNK_SIZE + i % CHUNK_SIZE,
+                    cy * CHUNK_SIZE + i / CHUNK_SIZE,
+                );
+                (cell, value)
+            })
+        })
+    }
+
+    pub fn log_odds(&self, cell: Cell) -> Scalar {
+        let (chunk, index) = GridMap::split(cell);
+        self.chunks.get(&chunk).map_or(0.0, |chunk| chunk[index])
+    }
+
+    /// Probability that the given cell is occupied.
+    pub fn probability(&self, cell: Cell) -> Scalar {
+        probability(self.log_odds(cell))
+    }
+
+    /// Classification of a cell with the given log-odds, by thresholding its
+    /// probability.
+    pub fn classify(&self, log_odds: Scalar) -> CellState {
+        let p = probability(log_odds);
+        if p > self.config.occupied_threshold {
+            CellState::Occupied
+        } else if p < self.config.free_threshold {
+            CellState::Freespace
+        } else {
+            CellState::Void
+        }
+    }
+
+    /// Classification of the given cell, by thresholding its probability.
+    pub fn cell_state(&self, cell: Cell) -> CellState {
+        self.classify(self.log_odds(cell))
+    }
+}
@@ -0,0 +1,242 @@
+//! Reading and writing grid maps in the format of the ROS `map_server`: a
+//! PGM image with the occupancy of each cell and a YAML file with metadata.
+//!
+//! Each pixel holds the occupancy probability `p` of a cell as
+//! `255 * (1 - p)`, i.e. occupied cells are black, free cells are white and
+//! unknown cells are gray.
+
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use geometry::Vector;
+use math::Scalar;
+
+use super::{log_odds, GridMap, OccupancyConfig};
+
+/// Write the grid map to `path` (YAML) and to an image file with the same
+/// name and the extension `.pgm`.
+pub fn save(gridmap: &GridMap, path: &Path) -> io::Result<()> {
+    let image_path = path.with_extension("pgm");
+    let image_name = image_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid map path"))?;
+
+    let (min, max) = gridmap.bounds().unwrap_or(((0, 0), (0, 0)));
+    let width = max.0 - min.0 + 1;
+    let height = max.1 - min.1 + 1;
+
+    let mut image = BufWriter::new(fs::File::create(&image_path)?);
+    write!(image, "P5\n{} {}\n255\n", width, height)?;
+
+    // The first row of the image is the top of the map
+    for r in (min.1..=max.1).rev() {
+        let row: Vec<u8> = (min.0..=max.0)
+            .map(|c| (255.0 * (1.0 - gridmap.probability((c, r)))).round() as u8)
+            .collect();
+        image.write_all(&row)?;
+    }
+    image.flush()?;
+
+    let resolution = gridmap.resolution();
+    let config = &gridmap.config;
+
+    let mut yaml = BufWriter::new(fs::File::create(path)?);
+    writeln!(yaml, "image: {}", image_name)?;
+    writeln!(yaml, "resolution: {}", resolution)?;
+    writeln!(
+        yaml,
+        "origin: [{}, {}, 0.0]",
+        min.0 as Scalar * resolution,
+        min.1 as Scalar * resolution
+    )?;
+    writeln!(yaml, "negate: 0")?;
+    writeln!(yaml, "occupied_thresh: {}", config.occupied_threshold)?;
+    writeln!(yaml, "free_thresh: {}", config.free_threshold)?;
+    yaml.flush()
+}
+
+/// Read a grid map from a YAML file (and the image it refers to). Cells
+/// between the free and the occupied threshold are unknown.
+pub fn load(path: &Path) -> Result<GridMap, String> {
+    let error = |e: &dyn ToString| format!("{}: {}", path.display(), e.to_string());
+
+    let yaml = fs::read_to_string(path).map_err(|e| error(&e))?;
+
+    let mut image = None;
+    let mut resolution = None;
+    let mut origin = Vector::new(0.0, 0.0);
+    let mut negate = false;
+    let mut config = OccupancyConfig::default();
+
+    for line in yaml.lines() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut parts = line.splitn(2, ':');
+        let key = parts.next().unwrap_or("").trim();
+        let value = match parts.next() {
+            Some(value) => value.trim(),
+            None => continue,
+        };
+
+        let number = |value: &str| {
+            value
+                .parse::<Scalar>()
+                .map_err(|_| error(&format!("invalid value for '{}'", key)))
+        };
+
+        match key {
+            "image" => image = Some(value.trim_matches('"').to_string()),
+            "resolution" => resolution = Some(number(value)?),
+            "origin" => {
+                let values = value
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .split(',')
+                    .map(|v| number(v.trim()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if values.len() != 3 {
+                    return Err(error(&"origin needs to be [x, y, yaw]"));
+                }
+                if values[2] != 0.0 {
+                    return Err(error(&"rotated maps are not supported"));
+                }
+                origin = Vector::new(values[0], values[1]);
+            }
+            "negate" => negate = number(value)? != 0.0,
+            "occupied_thresh" => config.occupied_threshold = number(value)?,
+            "free_thresh" => config.free_threshold = number(value)?,
+            _ => {}
+        }
+    }
+
+    let image = image.ok_or_else(|| error(&"missing 'image'"))?;
+    let resolution = resolution.ok_or_else(|| error(&"missing 'resolution'"))?;
+
+    let image_path = path.with_file_name(image);
+    let data = fs::read(&image_path).map_err(|e| format!("{}: {}", image_path.display(), e))?;
+    let (width, height, max_value, pixels) =
+        parse_pgm(&data).map_err(|e| format!("{}: {}", image_path.display(), e))?;
+
+    let mut gridmap = GridMap::new(resolution, config);
+
+    // The map origin is the lower left corner of the image
+    let offset = gridmap.cell_at(origin + Vector::new(0.5, 0.5) * resolution);
+
+    for (i, &value) in pixels.iter().enumerate() {
+        let (column, row) = ((i % width) as i64, (i / width) as i64);
+
+        let mut p = 1.0 - Scalar::from(value) / Scalar::from(max_value);
+        if negate {
+            p = 1.0 - p;
+        }
+
+        // Unknown cells are not stored, neither are cells that are neither
+        // free nor occupied
+        if p < gridmap.config.free_threshold || p > gridmap.config.occupied_threshold {
+            let cell = (offset.0 + column, offset.1 + (height as i64 - 1 - row));
+            gridmap.set_log_odds(cell, log_odds(p.clamp(0.001, 0.999)));
+        }
+    }
+
+    Ok(gridmap)
+}
+
+/// Parse a binary (P5) or plain (P2) 8-bit PGM image. Returns width, height,
+/// maximum value and the pixels, row by row from the top.
+fn parse_pgm(data: &[u8]) -> Result<(usize, usize, u16, Vec<u8>), String> {
+    let mut position = 0;
+    let invalid = |_| "invalid header".to_string();
+
+    let magic = next_token(data, &mut position)?;
+    let width: usize = next_token(data, &mut position)?.parse().map_err(invalid)?;
+    let height: usize = next_token(data, &mut position)?.parse().map_err(invalid)?;
+    let max_value: u16 = next_token(data, &mut position)?.parse().map_err(invalid)?;
+
+    if max_value == 0 || max_value > 255 {
+        return Err("only 8-bit images are supported".to_string());
+    }
+
+    let pixels = match magic.as_str() {
+        "P5" => {
+            // Exactly one whitespace character separates header and data
+            let start = position + 1;
+            data.get(start..start + width * height)
+                .ok_or_else(|| "image data too short".to_string())?
+                .to_vec()
+        }
+        "P2" => (0..width * height)
+            .map(|_| next_token(data, &mut position)?.parse().map_err(invalid))
+            .collect::<Result<Vec<u8>, String>>()?,
+        _ => return Err("not a PGM image".to_string()),
+    };
+
+    Ok((width, height, max_value, pixels))
+}
+
+/// Read the next whitespace-separated token of a PGM file, skipping comments.
+fn next_token(data: &[u8], position: &mut usize) -> Result<String, String> {
+    loop {
+        while *position < data.len() && data[*position].is_ascii_whitespace() {
+            *position += 1;
+        }
+        if *position < data.len() && data[*position] == b'#' {
+            while *position < data.len() && data[*position] != b'\n' {
+                *position += 1;
+            }
+        } else {
+            break;
+        }
+    }
+
+    let start = *position;
+    while *position < data.len() && !data[*position].is_ascii_whitespace() {
+        *position += 1;
+    }
+
+    if start == *position {
+        Err("unexpected end of file".to_string())
+    } else {
+        Ok(String::from_utf8_lossy(&data[start..*position]).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::process;
+
+    use controller::gridmap::{walls, CellState, CHUNK_SIZE, RESOLUTION};
+
+    #[test]
+    fn save_and_load() {
+        let walls = walls(&[(0.0, 0.0), (3.0, 0.0), (3.0, 2.0), (0.0, 2.0)]);
+        let mut gridmap = GridMap::from_walls(&walls, Vector::new(1.0, 1.0), RESOLUTION);
+
+        // A distant obstacle, with unknown space in between
+        gridmap.set_log_odds((10 * CHUNK_SIZE, 0), gridmap.config.max_log_odds);
+
+        let path = env::temp_dir().join(format!("gridmap-{}.yaml", process::id()));
+        save(&gridmap, &path).unwrap();
+        let loaded = load(&path);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(path.with_extension("pgm")).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.resolution(), gridmap.resolution());
+        assert_eq!(loaded.bounds(), gridmap.bounds());
+
+        let (min, max) = gridmap.bounds().unwrap();
+        for column in min.0..=max.0 {
+            for row in min.1..=max.1 {
+                let cell = (column, row);
+                assert_eq!(loaded.cell_state(cell), gridmap.cell_state(cell));
+            }
+        }
+        assert_eq!(loaded.cell_state((10 * CHUNK_SIZE, 0)), CellState::Occupied);
+
+        // The unknown space in between is not stored
+        assert_eq!(loaded.cells().count(), gridmap.cells().count());
+    }
+}
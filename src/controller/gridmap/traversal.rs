@@ -0,0 +1,178 @@
+//! Exact traversal of all grid cells that are crossed by a line segment
+//! (Amanatides & Woo, "A Fast Voxel Traversal Algorithm for Ray Tracing").
+
+use geometry::Vector;
+use math::Scalar;
+
+/// Index of a grid cell as (column, row). The cell (c, r) covers the square
+/// `[c, c + 1) x [r, r + 1)` in grid coordinates.
+pub type Cell = (i64, i64);
+
+/// The cell that contains the given point (in grid coordinates).
+pub fn cell_at(p: Vector) -> Cell {
+    (p.x.floor() as i64, p.y.floor() as i64)
+}
+
+/// Iterator over the cells crossed by a line segment, from the cell that
+/// contains the start point to the cell that contains the end point. Each
+/// cell is visited exactly once and consecutive cells share an edge.
+pub struct Traversal {
+    cell: Cell,
+    end: Cell,
+    step: (i64, i64),
+    /// Value of the line parameter at which the next column/row is entered
+    t_max: (Scalar, Scalar),
+    /// Change of the line parameter when crossing a full column/row
+    t_delta: (Scalar, Scalar),
+    remaining: usize,
+}
+
+impl Traversal {
+    /// Traverse the segment from `start` to `end`, both in grid coordinates.
+    pub fn new(start: Vector, end: Vector) -> Traversal {
+        let first = cell_at(start);
+        let last = cell_at(end);
+
+        let axis = |s: Scalar, d: Scalar| {
+            if d > 0.0 {
+                (1, (s.floor() + 1.0 - s) / d, 1.0 / d)
+            } else if d < 0.0 {
+                (-1, (s - s.floor()) / -d, -1.0 / d)
+            } else {
+                (0, Scalar::INFINITY, Scalar::INFINITY)
+            }
+        };
+
+        let direction = end - start;
+        let (step_x, t_max_x, t_delta_x) = axis(start.x, direction.x);
+        let (step_y, t_max_y, t_delta_y) = axis(start.y, direction.y);
+
+        // The number of cells is fixed by the start and end cells. Counting
+        // them (instead of comparing line parameters) makes sure that the
+        // traversal ends in the right cell, despite rounding errors.
+        let remaining = ((last.0 - first.0).abs() + (last.1 - first.1).abs() + 1) as usize;
+
+        Traversal {
+            cell: first,
+            end: last,
+            step: (step_x, step_y),
+            t_max: (t_max_x, t_max_y),
+            t_delta: (t_delta_x, t_delta_y),
+            remaining,
+        }
+    }
+}
+
+impl Iterator for Traversal {
+    type Item = Cell;
+
+    fn next(&mut self) -> Option<Cell> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let current = self.cell;
+
+        if self.remaining > 0 {
+            // Never step beyond the column or row of the last cell
+            let step_x = self.cell.1 == self.end.1
+                || (self.cell.0 != self.end.0 && self.t_max.0 < self.t_max.1);
+
+            if step_x {
+                self.cell.0 += self.step.0;
+                self.t_max.0 += self.t_delta.0;
+            } else {
+                self.cell.1 += self.step.1;
+                self.t_max.1 += self.t_delta.1;
+            }
+        }
+
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Traversal {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{Rng, SeedableRng, StdRng};
+    use std::collections::HashSet;
+
+    fn cells(start: (Scalar, Scalar), end: (Scalar, Scalar)) -> Vec<Cell> {
+        Traversal::new(Vector::new(start.0, start.1), Vector::new(end.0, end.1)).collect()
+    }
+
+    #[test]
+    fn single_cell() {
+        assert_eq!(cells((0.2, 0.3), (0.8, 0.9)), vec![(0, 0)]);
+        assert_eq!(cells((1.5, 1.5), (1.5, 1.5)), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn axis_aligned() {
+        assert_eq!(
+            cells((0.5, 0.5), (3.5, 0.5)),
+            vec![(0, 0), (1, 0), (2, 0), (3, 0)]
+        );
+        assert_eq!(
+            cells((0.5, 0.5), (0.5, -2.5)),
+            vec![(0, 0), (0, -1), (0, -2), (0, -3)]
+        );
+    }
+
+    #[test]
+    fn shallow_line() {
+        assert_eq!(
+            cells((0.5, 0.2), (3.5, 1.2)),
+            vec![(0, 0), (1, 0), (2, 0), (2, 1), (3, 1)]
+        );
+    }
+
+    #[test]
+    fn negative_direction() {
+        assert_eq!(
+            cells((3.5, 1.2), (0.5, 0.2)),
+            vec![(3, 1), (2, 1), (2, 0), (1, 0), (0, 0)]
+        );
+    }
+
+    /// Every cell that a random segment passes through is visited exactly
+    /// once, and consecutive cells are neighbors.
+    #[test]
+    fn visits_every_crossed_cell_once() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        for _ in 0..1000 {
+            let mut point = || Vector::new(rng.gen_range(-20.0, 20.0), rng.gen_range(-20.0, 20.0));
+            let start = point();
+            let end = point();
+
+            let visited: Vec<Cell> = Traversal::new(start, end).collect();
+
+            assert_eq!(visited.first(), Some(&cell_at(start)));
+            assert_eq!(visited.last(), Some(&cell_at(end)));
+
+            let unique: HashSet<Cell> = visited.iter().cloned().collect();
+            assert_eq!(unique.len(), visited.len());
+
+            for pair in visited.windows(2) {
+                let distance = (pair[0].0 - pair[1].0).abs() + (pair[0].1 - pair[1].1).abs();
+                assert_eq!(distance, 1);
+            }
+
+            let samples = 2_000;
+            for i in 0..=samples {
+                let alpha = Scalar::from(i) / Scalar::from(samples);
+                let p = start + (end - start) * alpha;
+                assert!(unique.contains(&cell_at(p)));
+            }
+        }
+    }
+}
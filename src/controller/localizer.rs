@@ -0,0 +1,59 @@
+use geometry::{Line, Pose};
+use math::Matrix3;
+use motion::Velocity;
+use sensor::odometry::DriveGeometry;
+use sensor::SensorReadings;
+
+use super::mcl::{MclConfig, MonteCarloLocalization};
+use super::wall_follower::WallSteering;
+use super::{Controller, DebugState};
+
+/// Follows the walls, and localizes the robot in a known map with a
+/// particle filter.
+pub struct Localizer {
+    pub mcl: MonteCarloLocalization,
+    pub drive_geometry: DriveGeometry,
+    driver: WallSteering,
+}
+
+impl Localizer {
+    /// Create a localizer for the given map, which is given relative to the
+    /// start pose of the robot. The start pose itself is unknown.
+    pub fn new(map: &[Line], seed: u64) -> Localizer {
+        Localizer {
+            mcl: MonteCarloLocalization::new(map, MclConfig::default(), seed),
+            drive_geometry: DriveGeometry::default(),
+            driver: WallSteering::default(),
+        }
+    }
+
+    /// Uncertainty of the pose estimate, as covariance of (x, y, heading).
+    pub fn covariance(&self) -> Matrix3 {
+        self.mcl.estimate().1
+    }
+}
+
+impl Controller for Localizer {
+    fn cycle(&mut self, readings: &SensorReadings) -> Velocity {
+        let motion = self.drive_geometry.to_motion(&readings.odometry);
+        self.mcl.predict(&motion);
+
+        if let Some(ref scan) = readings.scan {
+            self.mcl.correct(scan);
+        }
+
+        self.driver.steer(readings)
+    }
+
+    fn pose_estimate(&self) -> Pose {
+        self.mcl.estimate().0
+    }
+
+    fn debug_state(&self) -> DebugState<'_> {
+        DebugState {
+            particles: Some(&self.mcl.particles),
+            covariance: Some(self.covariance()),
+            ..DebugState::default()
+        }
+    }
+}
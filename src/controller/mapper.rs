@@ -0,0 +1,53 @@
+use geometry::Pose;
+use scanmatch::{IcpConfig, ScanToScan};
+use sensor::odometry::DriveGeometry;
+use sensor::SensorReadings;
+
+use super::gridmap::GridMap;
+
+/// Builds a grid map from laser scans, based on a pose that is estimated by
+/// dead reckoning and corrected by matching consecutive scans.
+pub struct Mapper {
+    pub gridmap: GridMap,
+    /// Estimated pose, relative to the pose at which the mapper started
+    pub pose_estimate: Pose,
+    pub drive_geometry: DriveGeometry,
+    /// Scan matcher, or `None` for pure dead reckoning
+    pub scan_matching: Option<ScanToScan>,
+}
+
+impl Default for Mapper {
+    fn default() -> Mapper {
+        Mapper {
+            gridmap: GridMap::default(),
+            pose_estimate: Pose::default(),
+            drive_geometry: DriveGeometry::default(),
+            scan_matching: Some(ScanToScan::new(IcpConfig::default(), 0.7)),
+        }
+    }
+}
+
+impl Mapper {
+    pub fn update(&mut self, readings: &SensorReadings) {
+        // Dead reckoning
+        self.pose_estimate = self
+            .drive_geometry
+            .to_motion(&readings.odometry)
+            .apply(&self.pose_estimate);
+
+        if let Some(ref scan) = readings.scan {
+            if let Some(ref mut matcher) = self.scan_matching {
+                let cloud = scan.to_pointcloud(&Pose::default());
+
+                // The odometry serves as initial guess
+                if let Some((pose, _)) = matcher.measure(&cloud, &self.pose_estimate) {
+                    self.pose_estimate = pose;
+                }
+
+                matcher.set_reference(&cloud, self.pose_estimate.clone());
+            }
+
+            self.gridmap.update(&self.pose_estimate, scan);
+        }
+    }
+}
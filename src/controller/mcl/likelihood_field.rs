@@ -0,0 +1,81 @@
+use geometry::{Line, Vector};
+use math::Scalar;
+
+/// Precomputed distance from each cell of a grid to the closest obstacle of
+/// a map, for the likelihood field measurement model.
+pub struct LikelihoodField {
+    /// Position of the lower left corner of the grid
+    origin: Vector,
+    resolution: Scalar,
+    width: usize,
+    height: usize,
+    distances: Vec<Scalar>,
+    /// Distances are only computed up to this value
+    max_distance: Scalar,
+}
+
+impl LikelihoodField {
+    pub fn new(map: &[Line], resolution: Scalar, max_distance: Scalar) -> LikelihoodField {
+        let mut min = Vector::new(Scalar::INFINITY, Scalar::INFINITY);
+        let mut max = Vector::new(Scalar::NEG_INFINITY, Scalar::NEG_INFINITY);
+        for line in map {
+            for p in &[line.start, line.end] {
+                min = Vector::new(min.x.min(p.x), min.y.min(p.y));
+                max = Vector::new(max.x.max(p.x), max.y.max(p.y));
+            }
+        }
+
+        if map.is_empty() {
+            min = Vector::new(0.0, 0.0);
+            max = min;
+        }
+
+        let margin = Vector::new(1.0, 1.0);
+        let origin = min - margin;
+        let size = max + margin - origin;
+        let width = (size.x / resolution).ceil() as usize;
+        let height = (size.y / resolution).ceil() as usize;
+
+        let mut distances = Vec::with_capacity(width * height);
+        for r in 0..height {
+            for c in 0..width {
+                let p = origin + Vector::new(c as Scalar + 0.5, r as Scalar + 0.5) * resolution;
+                let distance = map
+                    .iter()
+                    .map(|line| line.distance(p))
+                    .fold(max_distance, Scalar::min);
+                distances.push(distance);
+            }
+        }
+
+        LikelihoodField {
+            origin,
+            resolution,
+            width,
+            height,
+            distances,
+            max_distance,
+        }
+    }
+
+    /// Distance from `p` to the closest obstacle (at most `max_distance`).
+    pub fn distance(&self, p: Vector) -> Scalar {
+        let q = (p - self.origin) * (1.0 / self.resolution);
+        if q.x < 0.0 || q.y < 0.0 {
+            return self.max_distance;
+        }
+
+        let (c, r) = (q.x as usize, q.y as usize);
+        if c >= self.width || r >= self.height {
+            self.max_distance
+        } else {
+            self.distances[r * self.width + c]
+        }
+    }
+
+    /// Smallest and largest position covered by the field.
+    pub fn bounds(&self) -> (Vector, Vector) {
+        let size = Vector::new(self.width as Scalar, self.height as Scalar) * self.resolution;
+        (self.origin, self.origin + size)
+    }
+}
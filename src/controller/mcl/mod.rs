@@ -0,0 +1,327 @@
+//! Monte Carlo localization (particle filter) against a known map, see
+//! chapter 8.3 of "Probabilistic Robotics" by Thrun, Burgard and Fox.
+
+pub mod likelihood_field;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use geometry::{Line, Pose, Vector};
+use math::{normalize_angle, sample_normal, Matrix3, Scalar, PI};
+use motion::{Motion, MotionNoise};
+use sensor::laserscanner::Scan;
+
+use self::likelihood_field::LikelihoodField;
+
+/// A pose hypothesis with its importance weight.
+#[derive(Debug, Clone)]
+pub struct Particle {
+    pub pose: Pose,
+    pub weight: Scalar,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MclConfig {
+    /// Number of particles once the filter has converged
+    pub num_particles: usize,
+    /// Number of particles during global localization
+    pub max_particles: usize,
+    /// The filter is considered converged if the standard deviation of the
+    /// particle positions is below this value
+    pub converged_spread: Scalar,
+    /// Noise of the odometry motion model
+    pub motion_noise: MotionNoise,
+    /// Standard deviation of the measured distance to an obstacle
+    pub sigma_hit: Scalar,
+    /// Weight of the Gaussian measurement component
+    pub z_hit: Scalar,
+    /// Weight of the uniform measurement component (random measurements)
+    pub z_random: Scalar,
+    pub max_range: Scalar,
+    /// Only every n-th beam of a scan is used
+    pub beam_step: usize,
+    /// Exponent of the likelihood of each beam. Values below one account for
+    /// the correlation between neighboring beams, which would otherwise make
+    /// the filter overconfident.
+    pub beam_weight: Scalar,
+    /// Minimum motion (in meters or radians) between two measurement updates
+    pub update_distance: Scalar,
+    /// Decay rates of the long- and short-term average of the measurement
+    /// likelihood (augmented MCL). Random particles are injected when the
+    /// short-term average drops below the long-term average.
+    pub alpha_slow: Scalar,
+    pub alpha_fast: Scalar,
+    /// Random particles are placed at the most likely of this many uniformly
+    /// drawn poses, according to the current scan
+    pub injection_candidates: usize,
+    /// Particles are not placed closer to an obstacle than this
+    pub robot_radius: Scalar,
+}
+
+impl Default for MclConfig {
+    fn default() -> MclConfig {
+        MclConfig {
+            num_particles: 1000,
+            max_particles: 50000,
+            converged_spread: 0.5,
+            motion_noise: MotionNoise {
+                alpha1: 0.1,
+                alpha2: 0.05,
+                alpha3: 0.1,
+                alpha4: 0.05,
+            },
+            sigma_hit: 0.2,
+            z_hit: 0.9,
+            z_random: 0.1,
+            max_range: 8.0,
+            beam_step: 4,
+            beam_weight: 0.3,
+            update_distance: 0.1,
+            alpha_slow: 0.02,
+            alpha_fast: 0.1,
+            injection_candidates: 10,
+            robot_radius: 0.17,
+        }
+    }
+}
+
+pub struct MonteCarloLocalization {
+    pub config: MclConfig,
+    pub particles: Vec<Particle>,
+    field: LikelihoodField,
+    /// Motion since the last measurement update
+    motion_since_update: Scalar,
+    w_slow: Scalar,
+    w_fast: Scalar,
+    rng: StdRng,
+}
+
+impl MonteCarloLocalization {
+    /// Create a particle filter for the given map. The particles are spread
+    /// over the whole map (global localization).
+    pub fn new(map: &[Line], config: MclConfig, seed: u64) -> MonteCarloLocalization {
+        let mut mcl = MonteCarloLocalization {
+            config,
+            particles: vec![],
+            field: LikelihoodField::new(map, 0.05, 2.0),
+            motion_since_update: Scalar::INFINITY,
+            w_slow: 0.0,
+            w_fast: 0.0,
+            rng: StdRng::seed_from_u64(seed),
+        };
+        mcl.initialize_global();
+        mcl
+    }
+
+    /// Spread the particles uniformly over the free space of the map.
+    pub fn initialize_global(&mut self) {
+        let weight = 1.0 / self.config.max_particles as Scalar;
+        self.particles = (0..self.config.max_particles)
+            .map(|_| Particle {
+                pose: self.random_pose(),
+                weight,
+            })
+            .collect();
+    }
+
+    /// Place the particles around a known pose.
+    pub fn initialize_at(
+        &mut self,
+        pose: &Pose,
+        position_std_dev: Scalar,
+        heading_std_dev: Scalar,
+    ) {
+        let weight = 1.0 / self.config.num_particles as Scalar;
+        let rng = &mut self.rng;
+        self.particles = (0..self.config.num_particles)
+            .map(|_| {
+                let offset = Vector::new(
+                    sample_normal(rng, position_std_dev),
+                    sample_normal(rng, position_std_dev),
+                );
+                let heading = pose.heading + sample_normal(rng, heading_std_dev);
+                Particle {
+                    pose: Pose::new(pose.position + offset, heading),
+                    weight,
+                }
+            })
+            .collect();
+    }
+
+    fn random_pose(&mut self) -> Pose {
+        let (min, max) = self.field.bounds();
+        loop {
+            let position = Vector::new(
+                self.rng.gen_range(min.x, max.x),
+                self.rng.gen_range(min.y, max.y),
+            );
+            if self.field.distance(position) > self.config.robot_radius {
+                return Pose::new(position, self.rng.gen_range(-PI, PI));
+            }
+        }
+    }
+
+    /// The most likely of several random poses, given the scan.
+    fn likely_random_pose(&mut self, scan: &Scan) -> Pose {
+        let mut best = self.random_pose();
+        let mut best_likelihood = self.log_likelihood(&best, scan).0;
+        for _ in 1..self.config.injection_candidates {
+            let pose = self.random_pose();
+            let likelihood = self.log_likelihood(&pose, scan).0;
+            if likelihood > best_likelihood {
+                best = pose;
+                best_likelihood = likelihood;
+            }
+        }
+        best
+    }
+
+    /// Move all particles according to the (noisy) odometry motion model.
+    pub fn predict(&mut self, motion: &Motion) {
+        for particle in &mut self.particles {
+            let sample = self.config.motion_noise.sample(motion, &mut self.rng);
+            particle.pose = sample.apply(&particle.pose);
+        }
+
+        self.motion_since_update +=
+            motion.translation.abs() + motion.rotation1.abs() + motion.rotation2.abs();
+    }
+
+    /// Weight the particles with the likelihood of the scan and resample.
+    /// The update is skipped if the robot has not moved enough since the
+    /// last update, to avoid a premature convergence of the filter.
+    pub fn correct(&mut self, scan: &Scan) {
+        if self.motion_since_update < self.config.update_distance {
+            return;
+        }
+        self.motion_since_update = 0.0;
+
+        // Average likelihood of the measurement per beam. Unlike the total
+        // likelihood, this does not depend on the number of beams that hit
+        // an obstacle. Particle weights are normalized after each resampling
+        // step.
+        let mut average = 0.0;
+
+        let mut total = 0.0;
+        for i in 0..self.particles.len() {
+            let (log_likelihood, num_beams) = self.log_likelihood(&self.particles[i].pose, scan);
+            let particle = &mut self.particles[i];
+
+            average += particle.weight * (log_likelihood / num_beams.max(1) as Scalar).exp();
+
+            particle.weight *= (self.config.beam_weight * log_likelihood).exp();
+            total += particle.weight;
+        }
+
+        if total <= 0.0 || !total.is_finite() {
+            // All particles are inconsistent with the measurement
+            self.initialize_global();
+            return;
+        }
+
+        if self.w_slow == 0.0 {
+            self.w_slow = average;
+            self.w_fast = average;
+        } else {
+            self.w_slow += self.config.alpha_slow * (average - self.w_slow);
+            self.w_fast += self.config.alpha_fast * (average - self.w_fast);
+        }
+
+        for particle in &mut self.particles {
+            particle.weight /= total;
+        }
+
+        self.resample(scan);
+    }
+
+    /// Log-likelihood of the scan, if taken at the given pose, and the number
+    /// of beams that were taken into account.
+    fn log_likelihood(&self, pose: &Pose, scan: &Scan) -> (Scalar, usize) {
+        let config = &self.config;
+        let normalization = 1.0 / ((2.0 * PI).sqrt() * config.sigma_hit);
+
+        scan.hits()
+            .step_by(config.beam_step.max(1))
+            .filter(|m| m.distance < config.max_range)
+            .map(|m| {
+                let distance = self.field.distance(m.to_vector(pose));
+                let p_hit = normalization * (-0.5 * (distance / config.sigma_hit).powi(2)).exp();
+                (config.z_hit * p_hit + config.z_random / config.max_range).ln()
+            })
+            .fold((0.0, 0), |(sum, n), l| (sum + l, n + 1))
+    }
+
+    /// Low-variance resampling, with random particles for recovery from
+    /// localization failures (e.g. a kidnapped robot).
+    fn resample(&mut self, scan: &Scan) {
+        let p_random = (1.0 - self.w_fast / self.w_slow).max(0.0);
+
+        // Use as many particles as needed to cover the belief
+        let covariance = self.estimate().1;
+        let spread = (covariance.m[0][0] + covariance.m[1][1]).sqrt();
+        let n = if spread < self.config.converged_spread && p_random == 0.0 {
+            self.config.num_particles
+        } else {
+            self.config.max_particles
+        };
+
+        let step = 1.0 / n as Scalar;
+        let mut target = self.rng.gen_range(0.0, step);
+        let mut cumulative = 0.0;
+        let mut i = 0;
+
+        let mut resampled = Vec::with_capacity(n);
+        for _ in 0..n {
+            if self.rng.gen_bool(p_random.min(1.0)) {
+                resampled.push(Particle {
+                    pose: self.likely_random_pose(scan),
+                    weight: step,
+                });
+                target += step;
+                continue;
+            }
+
+            while i < self.particles.len() - 1 && cumulative + self.particles[i].weight < target {
+                cumulative += self.particles[i].weight;
+                i += 1;
+            }
+
+            resampled.push(Particle {
+                pose: self.particles[i].pose.clone(),
+                weight: step,
+            });
+            target += step;
+        }
+
+        self.particles = resampled;
+    }
+
+    /// Weighted mean of the particles and its covariance (x, y, heading).
+    pub fn estimate(&self) -> (Pose, Matrix3) {
+        let total: Scalar = self.particles.iter().map(|p| p.weight).sum();
+        let zero = Vector::new(0.0, 0.0);
+        if self.particles.is_empty() || total <= 0.0 {
+            return (Pose::default(), Matrix3::zero());
+        }
+
+        let position = self
+            .particles
+            .iter()
+            .fold(zero, |s, p| s + p.pose.position * p.weight)
+            * (1.0 / total);
+
+        // Circular mean of the headings
+        let direction = self.particles.iter().fold(zero, |s, p| {
+            s + Vector::from_angle(p.pose.heading) * p.weight
+        });
+        let heading = direction.angle();
+
+        let mut covariance = Matrix3::zero();
+        for p in &self.particles {
+            let d = p.pose.position - position;
+            let e = [d.x, d.y, normalize_angle(p.pose.heading - heading)];
+            covariance = covariance + Matrix3::outer(e, e) * (p.weight / total);
+        }
+
+        (Pose::new(position, heading), covariance)
+    }
+}
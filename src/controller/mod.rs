@@ -1,31 +1,146 @@
+pub mod bump_and_turn;
+pub mod cleaner;
+pub mod coverage;
+pub mod ekf;
+pub mod explorer;
 pub mod gridmap;
+pub mod localizer;
+pub mod mapper;
+pub mod mcl;
+pub mod path_follower;
+pub mod planner;
+pub mod random_bounce;
+pub mod slam;
+pub mod slam_mapper;
+pub mod wall_follower;
 
-use geometry::Pose;
-use sensor::laserscanner::Scan;
+use geometry::{Line, Pose};
+use math::{normalize_angle, Angle, Matrix3, Scalar};
+use motion::Velocity;
+use sensor::SensorReadings;
 
+use self::bump_and_turn::BumpAndTurn;
+use self::cleaner::Cleaner;
+use self::ekf::EkfLocalizer;
+use self::explorer::{Explorer, Navigation};
 use self::gridmap::GridMap;
+use self::localizer::Localizer;
+use self::mcl::Particle;
+use self::planner::Path;
+use self::random_bounce::RandomBounce;
+use self::slam_mapper::SlamMapper;
+use self::wall_follower::WallFollower;
 
-pub struct Controller {
-    pub gridmap: GridMap,
-    pub pose_estimate: Pose,
+/// Internal state of a controller that can be visualized.
+#[derive(Default)]
+pub struct DebugState<'a> {
+    pub gridmap: Option<&'a GridMap>,
+    /// Particles of a particle filter, relative to the start pose
+    pub particles: Option<&'a [Particle]>,
+    /// Uncertainty of the pose estimate, as covariance of (x, y, heading)
+    pub covariance: Option<Matrix3>,
+    /// Path that the controller follows, relative to the start pose
+    pub path: Option<&'a Path>,
 }
 
-impl Default for Controller {
-    fn default() -> Controller {
-        Controller {
-            gridmap: GridMap::default(),
-            pose_estimate: Pose::default(),
+pub trait Controller {
+    /// Process the sensor readings of one cycle and decide on the velocity
+    /// to drive with until the next cycle.
+    fn cycle(&mut self, readings: &SensorReadings) -> Velocity;
+
+    /// Estimated pose, relative to the pose at which the controller started.
+    fn pose_estimate(&self) -> Pose;
+
+    fn debug_state(&self) -> DebugState<'_> {
+        DebugState::default()
+    }
+
+    /// Replace the known map of the controller, which is given relative to
+    /// the start pose (e.g. a map that was saved in an earlier run). Returns
+    /// `false` if the controller does not use a known grid map.
+    fn set_map(&mut self, _gridmap: GridMap) -> bool {
+        false
+    }
+
+    /// Whether the controller has completed its task (e.g. exploration).
+    fn is_finished(&self) -> bool {
+        false
+    }
+}
+
+/// Names of all built-in controllers. The first one is the default.
+pub const NAMES: &[&str] = &[
+    "bump-and-turn",
+    "random-bounce",
+    "wall-follower",
+    "localizer",
+    "ekf",
+    "slam",
+    "explorer",
+    "planning-explorer",
+    "cleaner",
+];
+
+/// Construct one of the built-in controllers. Controllers that need a known
+/// map get `map`, which is given relative to the start pose of the robot.
+pub fn from_name(name: &str, seed: u64, map: &[Line]) -> Option<Box<dyn Controller>> {
+    match name {
+        "bump-and-turn" => Some(Box::new(BumpAndTurn::default())),
+        "random-bounce" => Some(Box::new(RandomBounce::new(seed))),
+        "wall-follower" => Some(Box::new(WallFollower::default())),
+        "localizer" => Some(Box::new(Localizer::new(map, seed))),
+        "ekf" => Some(Box::new(EkfLocalizer::default())),
+        "slam" => Some(Box::new(SlamMapper::default())),
+        "explorer" => Some(Box::new(Explorer::default())),
+        "planning-explorer" => {
+            let mut explorer = Explorer::default();
+            explorer.navigation = Some(Navigation::default());
+            Some(Box::new(explorer))
         }
+        "cleaner" => Some(Box::new(Cleaner::new(map, seed))),
+        _ => None,
     }
 }
 
-impl Controller {
-    pub fn cycle(&mut self, scan: &Scan, pose_todo: &Pose) {
-        // self.gridmap.clear();
+/// Turn in place towards the target heading. Returns `None` once the target
+/// heading has been reached.
+pub fn turn_towards(heading: Angle, target: Angle, turn_rate: Angle) -> Option<Velocity> {
+    let error = normalize_angle(target - heading);
+    if error.abs() < 0.1 {
+        None
+    } else {
+        Some(Velocity::new(0.0, turn_rate.copysign(error)))
+    }
+}
+
+/// Makes the robot back off for a moment after each collision, so that it
+/// gets clear of the obstacle before it continues.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BackOff {
+    /// The robot backs off until this time
+    until: Scalar,
+}
 
-        // TODO: this is cheating
-        self.pose_estimate = pose_todo.clone();
+impl BackOff {
+    /// Duration of backing off, in seconds
+    pub const DURATION: Scalar = 0.5;
 
-        self.gridmap.update(&self.pose_estimate, scan);
+    /// Velocity for backing off at half the given speed, or `None` if the
+    /// robot has not collided recently.
+    pub fn velocity(&mut self, readings: &SensorReadings, speed: Scalar) -> Option<Velocity> {
+        if readings.bumper.any() {
+            self.until = readings.time + BackOff::DURATION;
+        }
+        if readings.time < self.until {
+            Some(Velocity::new(-0.5 * speed, 0.0))
+        } else {
+            None
+        }
     }
 }
+
+/// Default forward speed of the built-in controllers, in m/s.
+pub const SPEED: Scalar = 0.3;
+
+/// Default turn rate of the built-in controllers, in rad/s.
+pub const TURN_RATE: Angle = 1.5;
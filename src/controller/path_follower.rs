@@ -0,0 +1,158 @@
+use geometry::{Pose, Vector};
+use math::{normalize_angle, Angle, Scalar, PI};
+use motion::Velocity;
+
+use super::planner::Path;
+use super::{SPEED, TURN_RATE};
+
+/// Tracks a path with the pure pursuit algorithm: the robot steers on a
+/// circular arc towards the point of the path that is one lookahead distance
+/// ahead.
+pub struct PathFollower {
+    /// Forward speed in m/s
+    pub speed: Scalar,
+    /// Turn rate for turning in place, in rad/s
+    pub turn_rate: Angle,
+    /// Distance between the robot and the point it steers towards
+    pub lookahead: Scalar,
+    /// The robot turns in place if the point it steers towards is at a
+    /// larger angle than this
+    pub max_heading_error: Angle,
+    /// The end of the path counts as reached within this distance
+    pub goal_tolerance: Scalar,
+    path: Path,
+    /// Index of the segment of the path that the robot follows. It never
+    /// decreases, so that the robot does not take shortcuts where the path
+    /// comes back to itself.
+    segment: usize,
+}
+
+impl Default for PathFollower {
+    fn default() -> PathFollower {
+        PathFollower {
+            speed: SPEED,
+            turn_rate: TURN_RATE,
+            lookahead: 0.3,
+            max_heading_error: PI / 4.0,
+            goal_tolerance: 0.1,
+            path: Path::default(),
+            segment: 0,
+        }
+    }
+}
+
+impl PathFollower {
+    pub fn set_path(&mut self, path: Path) {
+        self.path = path;
+        self.segment = 0;
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn clear(&mut self) {
+        self.set_path(Path::default());
+    }
+
+    /// Whether there is no path, or the end of the path has been reached.
+    pub fn is_done(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Velocity to follow the path from the given pose. Returns `None` (and
+    /// clears the path) once its end has been reached.
+    pub fn follow(&mut self, pose: &Pose) -> Option<Velocity> {
+        let end = *self.path.waypoints.last()?;
+        if (end - pose.position).length() < self.goal_tolerance {
+            self.clear();
+            return None;
+        }
+
+        let target = self.lookahead_point(pose.position);
+        let offset = target - pose.position;
+        let error = normalize_angle(offset.angle() - pose.heading);
+
+        if error.abs() > self.max_heading_error {
+            return Some(Velocity::new(0.0, self.turn_rate.copysign(error)));
+        }
+
+        // Curvature of the arc through the target, tangential to the heading.
+        // Slow down close to the end of the path.
+        let distance = offset.length();
+        let curvature = 2.0 * error.sin() / distance;
+        let speed = self
+            .speed
+            .min((end - pose.position).length() + self.goal_tolerance);
+
+        Some(Velocity::new(speed, speed * curvature))
+    }
+
+    /// Advance the current segment, and find the point on the path that is
+    /// one lookahead distance from `position`. Only the current segment and
+    /// the ones after it are considered.
+    fn lookahead_point(&mut self, position: Vector) -> Vector {
+        let waypoints = &self.path.waypoints;
+        if waypoints.len() < 2 {
+            return waypoints[0];
+        }
+
+        // Skip the segments that the robot has passed, i.e. as long as the
+        // next segment is at least as close
+        let distance_to = |i: usize| closest_on_segment(waypoints[i], waypoints[i + 1], position);
+        while self.segment + 2 < waypoints.len()
+            && (distance_to(self.segment + 1) - position).length()
+                <= (distance_to(self.segment) - position).length()
+        {
+            self.segment += 1;
+        }
+
+        for i in self.segment..waypoints.len() - 1 {
+            let (a, b) = (waypoints[i], waypoints[i + 1]);
+            if let Some(p) = circle_intersection(a, b, position, self.lookahead) {
+                self.segment = i;
+                return p;
+            }
+            if (b - position).length() > self.lookahead {
+                // The robot is further away from the path than the lookahead
+                // distance
+                self.segment = i;
+                return b;
+            }
+        }
+
+        // The end of the path is within the lookahead distance
+        *waypoints.last().unwrap()
+    }
+}
+
+/// The point on the segment from `a` to `b` that is closest to `p`.
+fn closest_on_segment(a: Vector, b: Vector, p: Vector) -> Vector {
+    let d = b - a;
+    let length_squared = d.dot(d);
+    if length_squared == 0.0 {
+        return a;
+    }
+    let t = ((p - a).dot(d) / length_squared).clamp(0.0, 1.0);
+    a + d * t
+}
+
+/// The last intersection of the segment from `a` to `b` with the circle of
+/// the given radius around `center`, if any.
+fn circle_intersection(a: Vector, b: Vector, center: Vector, radius: Scalar) -> Option<Vector> {
+    let d = b - a;
+    let f = a - center;
+
+    let (qa, qb, qc) = (d.dot(d), 2.0 * f.dot(d), f.dot(f) - radius * radius);
+    let discriminant = qb * qb - 4.0 * qa * qc;
+    if qa == 0.0 || discriminant < 0.0 {
+        return None;
+    }
+
+    let t = (-qb + discriminant.sqrt()) / (2.0 * qa);
+    if (0.0..=1.0).contains(&t) {
+        Some(a + d * t)
+    } else {
+        None
+    }
+}
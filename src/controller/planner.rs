@@ -0,0 +1,415 @@
+//! Path planning on the occupancy grid with A*.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use geometry::{Pose, Vector};
+use math::Scalar;
+
+use super::gridmap::traversal::{Cell, Traversal};
+use super::gridmap::{CellState, GridMap};
+
+/// The eight neighbors of a cell, the direct ones first.
+pub const NEIGHBORS: [Cell; 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// A path as a sequence of waypoints, connected by straight lines.
+#[derive(Debug, Clone, Default)]
+pub struct Path {
+    pub waypoints: Vec<Vector>,
+}
+
+impl Path {
+    pub fn new(waypoints: Vec<Vector>) -> Path {
+        Path { waypoints }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waypoints.is_empty()
+    }
+
+    pub fn length(&self) -> Scalar {
+        self.waypoints
+            .windows(2)
+            .map(|w| (w[1] - w[0]).length())
+            .sum()
+    }
+
+    /// Transform a path that is given relative to the pose.
+    pub fn transform(&self, pose: &Pose) -> Path {
+        Path::new(self.waypoints.iter().map(|&p| pose.transform(p)).collect())
+    }
+}
+
+/// A grid map with inflated obstacles. Cells that are closer to an obstacle
+/// than the robot radius can not be entered. Cells that are closer than the
+/// inflation radius can be entered, but at a cost that keeps paths away from
+/// obstacles where possible.
+pub struct CostMap<'a> {
+    pub gridmap: &'a GridMap,
+    pub robot_radius: Scalar,
+    pub inflation_radius: Scalar,
+    /// Whether unexplored cells can be entered
+    pub allow_unknown: bool,
+    /// Distance to the closest occupied cell (up to the inflation radius)
+    distances: HashMap<Cell, Scalar>,
+}
+
+impl<'a> CostMap<'a> {
+    pub fn new(
+        gridmap: &'a GridMap,
+        robot_radius: Scalar,
+        inflation_radius: Scalar,
+    ) -> CostMap<'a> {
+        let resolution = gridmap.resolution();
+        let max_distance = inflation_radius.max(robot_radius);
+
+        // Propagate the closest obstacle from cell to cell, starting at all
+        // occupied cells ("brushfire")
+        let mut closest: HashMap<Cell, Cell> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for (cell, log_odds) in gridmap.cells() {
+            if gridmap.classify(log_odds) == CellState::Occupied {
+                closest.insert(cell, cell);
+                queue.push_back(cell);
+            }
+        }
+
+        let distance = |a: Cell, b: Cell| {
+            let (dx, dy) = ((a.0 - b.0) as Scalar, (a.1 - b.1) as Scalar);
+            (dx * dx + dy * dy).sqrt() * resolution
+        };
+
+        while let Some(cell) = queue.pop_front() {
+            let source = closest[&cell];
+            for &(dx, dy) in &NEIGHBORS {
+                let next = (cell.0 + dx, cell.1 + dy);
+                let d = distance(next, source);
+                if d > max_distance {
+                    continue;
+                }
+
+                let improves = match closest.get(&next) {
+                    Some(&other) => d < distance(next, other),
+                    None => true,
+                };
+                if improves {
+                    closest.insert(next, source);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let distances = closest
+            .into_iter()
+            .map(|(cell, source)| (cell, distance(cell, source)))
+            .collect();
+
+        CostMap {
+            gridmap,
+            robot_radius,
+            inflation_radius,
+            allow_unknown: false,
+            distances,
+        }
+    }
+
+    /// Distance from the cell to the closest obstacle, or `None` if it is
+    /// further away than the inflation radius.
+    pub fn obstacle_distance(&self, cell: Cell) -> Option<Scalar> {
+        self.distances.get(&cell).cloned()
+    }
+
+    /// Whether the robot can be placed at the given cell.
+    pub fn is_free(&self, cell: Cell) -> bool {
+        let known = match self.gridmap.cell_state(cell) {
+            CellState::Freespace => true,
+            CellState::Void => self.allow_unknown,
+            CellState::Occupied => false,
+        };
+        known
+            && match self.obstacle_distance(cell) {
+                Some(d) => d >= self.robot_radius,
+                None => true,
+            }
+    }
+
+    /// Additional cost for entering the given cell, between zero (far from
+    /// obstacles) and one (at the robot radius).
+    pub fn cost(&self, cell: Cell) -> Scalar {
+        match self.obstacle_distance(cell) {
+            Some(d) if self.inflation_radius > self.robot_radius => {
+                let t = (d - self.robot_radius) / (self.inflation_radius - self.robot_radius);
+                (1.0 - t).clamp(0.0, 1.0).powi(2)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Whether the straight line between two positions only crosses free
+    /// cells.
+    pub fn line_of_sight(&self, from: Vector, to: Vector) -> bool {
+        let scale = 1.0 / self.gridmap.resolution();
+        Traversal::new(from * scale, to * scale).all(|cell| self.is_free(cell))
+    }
+}
+
+/// Searches shortest collision-free paths with A*.
+#[derive(Debug, Clone, Copy)]
+pub struct Planner {
+    /// Relative weight of the obstacle cost, compared to the path length
+    pub cost_weight: Scalar,
+    /// Shorten the path by connecting waypoints with a line of sight
+    pub smoothing: bool,
+}
+
+impl Default for Planner {
+    fn default() -> Planner {
+        Planner {
+            cost_weight: 5.0,
+            smoothing: true,
+        }
+    }
+}
+
+/// Entry of the open list of A*, ordered by the estimated total cost (the
+/// smallest first).
+struct Candidate {
+    estimate: Scalar,
+    cell: Cell,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Candidate) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Planner {
+    /// Plan a path from `start` to `goal` (positions in the frame of the
+    /// grid map). Returns `None` if the goal can not be reached.
+    ///
+    /// The robot may start within the robot radius of an obstacle, e.g. after
+    /// a collision. It can then leave that area, but not enter it again.
+    pub fn plan(&self, costmap: &CostMap<'_>, start: Vector, goal: Vector) -> Option<Path> {
+        let gridmap = costmap.gridmap;
+        let resolution = gridmap.resolution();
+        let (start_cell, goal_cell) = (gridmap.cell_at(start), gridmap.cell_at(goal));
+        if !costmap.is_free(goal_cell) {
+            return None;
+        }
+
+        // Octile distance, an admissible heuristic for 8-connected grids
+        let heuristic = |cell: Cell| {
+            let (dx, dy) = ((cell.0 - goal_cell.0).abs(), (cell.1 - goal_cell.1).abs());
+            let (min, max) = (dx.min(dy) as Scalar, dx.max(dy) as Scalar);
+            (max + (2.0_f64.sqrt() - 1.0) * min) * resolution
+        };
+
+        let escape_radius = (costmap.robot_radius / resolution).ceil() as i64;
+        let can_enter = |from: Cell, to: Cell| {
+            if costmap.is_free(to) {
+                return true;
+            }
+
+            // Leaving the surroundings of an obstacle, near the start
+            let near_start =
+                (to.0 - start_cell.0).abs().max((to.1 - start_cell.1).abs()) <= escape_radius;
+            let clearance = |cell| costmap.obstacle_distance(cell).unwrap_or(Scalar::INFINITY);
+            near_start
+                && gridmap.cell_state(to) == CellState::Freespace
+                && clearance(to) >= clearance(from)
+        };
+
+        let mut costs: HashMap<Cell, Scalar> = HashMap::new();
+        let mut parents: HashMap<Cell, Cell> = HashMap::new();
+        let mut open = BinaryHeap::new();
+
+        costs.insert(start_cell, 0.0);
+        open.push(Candidate {
+            estimate: heuristic(start_cell),
+            cell: start_cell,
+        });
+
+        while let Some(Candidate { estimate, cell }) = open.pop() {
+            if cell == goal_cell {
+                break;
+            }
+
+            let cost = costs[&cell];
+            if estimate > cost + heuristic(cell) {
+                // Outdated entry
+                continue;
+            }
+
+            for &(dx, dy) in &NEIGHBORS {
+                let next = (cell.0 + dx, cell.1 + dy);
+                if !can_enter(cell, next) {
+                    continue;
+                }
+
+                let step = if dx == 0 || dy == 0 {
+                    1.0
+                } else {
+                    2.0_f64.sqrt()
+                };
+                let next_cost =
+                    cost + step * resolution * (1.0 + self.cost_weight * costmap.cost(next));
+
+                let improves = match costs.get(&next) {
+                    Some(&c) => next_cost < c,
+                    None => true,
+                };
+                if improves {
+                    costs.insert(next, next_cost);
+                    parents.insert(next, cell);
+                    open.push(Candidate {
+                        estimate: next_cost + heuristic(next),
+                        cell: next,
+                    });
+                }
+            }
+        }
+
+        if !costs.contains_key(&goal_cell) {
+            return None;
+        }
+
+        let mut cells = vec![goal_cell];
+        while let Some(&parent) = parents.get(cells.last().unwrap()) {
+            cells.push(parent);
+        }
+        cells.reverse();
+
+        let mut waypoints: Vec<Vector> = cells.iter().map(|&c| gridmap.cell_center(c)).collect();
+        waypoints[0] = start;
+        *waypoints.last_mut().unwrap() = goal;
+
+        if self.smoothing {
+            waypoints = smooth(costmap, &waypoints);
+        }
+
+        Some(Path::new(waypoints))
+    }
+}
+
+/// Remove waypoints that can be skipped: each waypoint is connected with
+/// the last of the following waypoints that are in line of sight.
+pub fn smooth(costmap: &CostMap<'_>, waypoints: &[Vector]) -> Vec<Vector> {
+    let mut result = vec![];
+    let mut i = 0;
+
+    while i < waypoints.len() {
+        result.push(waypoints[i]);
+
+        let mut next = i + 1;
+        while next + 1 < waypoints.len() && costmap.line_of_sight(waypoints[i], waypoints[next + 1])
+        {
+            next += 1;
+        }
+        i = next;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use controller::gridmap::{walls, RESOLUTION};
+    use geometry::Line;
+
+    /// A room of 4 m x 3 m, with a wall at x = 2 m that leaves a gap of the
+    /// given width at the top.
+    fn room(gap: Scalar) -> GridMap {
+        let mut lines = walls(&[(0.0, 0.0), (4.0, 0.0), (4.0, 3.0), (0.0, 3.0)]);
+        lines.push(Line::new(
+            Vector::new(2.0, 0.0),
+            Vector::new(2.0, 3.0 - gap),
+        ));
+        GridMap::from_walls(&lines, Vector::new(1.0, 0.5), RESOLUTION)
+    }
+
+    #[test]
+    fn straight_path_in_open_space() {
+        let gridmap = room(1.0);
+        let costmap = CostMap::new(&gridmap, 0.2, 0.4);
+
+        let (start, goal) = (Vector::new(0.5, 1.0), Vector::new(1.5, 1.5));
+        let path = Planner::default().plan(&costmap, start, goal).unwrap();
+
+        assert_eq!(path.waypoints, vec![start, goal]);
+    }
+
+    #[test]
+    fn path_around_wall_keeps_clearance() {
+        let gridmap = room(1.0);
+        let robot_radius = 0.2;
+        let costmap = CostMap::new(&gridmap, robot_radius, 0.4);
+
+        let (start, goal) = (Vector::new(1.0, 0.5), Vector::new(3.0, 0.5));
+        let path = Planner::default().plan(&costmap, start, goal).unwrap();
+
+        assert_eq!(path.waypoints.first(), Some(&start));
+        assert_eq!(path.waypoints.last(), Some(&goal));
+
+        // The path leads through the gap above the wall
+        assert!(path.waypoints.iter().any(|p| p.y > 2.0));
+        assert!(path.length() > 3.5);
+
+        let wall = Line::new(Vector::new(2.0, 0.0), Vector::new(2.0, 2.0));
+        for pair in path.waypoints.windows(2) {
+            let samples = 100;
+            for i in 0..=samples {
+                let alpha = Scalar::from(i) / Scalar::from(samples);
+                let p = pair[0] + (pair[1] - pair[0]) * alpha;
+                assert!(wall.distance(p) >= robot_radius - RESOLUTION);
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let planner = Planner::default();
+
+        // The gap is narrower than the robot
+        let gridmap = room(0.3);
+        let costmap = CostMap::new(&gridmap, 0.2, 0.4);
+        assert!(planner
+            .plan(&costmap, Vector::new(1.0, 0.5), Vector::new(3.0, 0.5))
+            .is_none());
+
+        // The goal is too close to a wall
+        let gridmap = room(1.0);
+        let costmap = CostMap::new(&gridmap, 0.2, 0.4);
+        assert!(planner
+            .plan(&costmap, Vector::new(1.0, 0.5), Vector::new(1.0, 0.1))
+            .is_none());
+    }
+}
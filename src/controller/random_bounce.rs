@@ -0,0 +1,73 @@
+use rand::{Rng, SeedableRng, StdRng};
+
+use geometry::Pose;
+use math::{Angle, Scalar, PI};
+use motion::Velocity;
+use sensor::SensorReadings;
+
+use super::mapper::Mapper;
+use super::{turn_towards, Controller, DebugState, SPEED, TURN_RATE};
+
+/// Drives straight until one of the bumpers is hit, then turns away from the
+/// obstacle by a random angle.
+pub struct RandomBounce {
+    pub mapper: Mapper,
+    /// Forward speed in m/s
+    pub speed: Scalar,
+    /// Turn rate in rad/s
+    pub turn_rate: Angle,
+    /// Heading (estimate) to turn to before driving on
+    turn_target: Option<Angle>,
+    rng: StdRng,
+}
+
+impl RandomBounce {
+    pub fn new(seed: u64) -> RandomBounce {
+        RandomBounce {
+            mapper: Mapper::default(),
+            speed: SPEED,
+            turn_rate: TURN_RATE,
+            turn_target: None,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Controller for RandomBounce {
+    fn cycle(&mut self, readings: &SensorReadings) -> Velocity {
+        self.mapper.update(readings);
+        let heading = self.mapper.pose_estimate.heading;
+
+        let bumper = &readings.bumper;
+        if bumper.any() && self.turn_target.is_none() {
+            let angle = self.rng.gen_range(PI / 4.0, 3.0 * PI / 4.0);
+
+            // Turn right if the obstacle is on the left, and vice versa
+            if bumper.left || (bumper.center && self.rng.gen()) {
+                self.turn_target = Some(heading - angle);
+            } else {
+                self.turn_target = Some(heading + angle);
+            }
+        }
+
+        if let Some(target) = self.turn_target {
+            if let Some(velocity) = turn_towards(heading, target, self.turn_rate) {
+                return velocity;
+            }
+            self.turn_target = None;
+        }
+
+        Velocity::new(self.speed, 0.0)
+    }
+
+    fn pose_estimate(&self) -> Pose {
+        self.mapper.pose_estimate.clone()
+    }
+
+    fn debug_state(&self) -> DebugState<'_> {
+        DebugState {
+            gridmap: Some(&self.mapper.gridmap),
+            ..DebugState::default()
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Graph-based SLAM: the poses of keyframes are connected by scan matches,
+//! including loop closures against older keyframes, and are estimated by
+//! optimizing the pose graph.
+
+pub mod pose_graph;
+
+use geometry::Pose;
+use math::{normalize_angle, Angle, Matrix3, Scalar};
+use pointcloud::PointCloud;
+use scanmatch::{self, IcpConfig, MatchResult, Reference, ScanToScan};
+use sensor::laserscanner::Scan;
+use sensor::odometry::DriveGeometry;
+use sensor::SensorReadings;
+
+use super::gridmap::GridMap;
+
+use self::pose_graph::PoseGraph;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SlamConfig {
+    /// A new keyframe is created once the robot has moved this far since
+    /// the last keyframe ...
+    pub keyframe_distance: Scalar,
+    /// ... or turned by this angle
+    pub keyframe_angle: Angle,
+    /// Standard deviation of a scan match without residual error (in meters
+    /// and radians)
+    pub position_std_dev: Scalar,
+    pub heading_std_dev: Angle,
+    /// Uncertainty of the odometry between two keyframes, relative to that
+    /// of a scan match. Used if the scans of the keyframes do not match.
+    pub odometry_scale: Scalar,
+    /// Older keyframes within this distance of a new keyframe are matched
+    /// against it to detect loop closures
+    pub loop_closure_radius: Scalar,
+    /// Number of most recent keyframes that are not considered for loop
+    /// closures
+    pub loop_closure_gap: usize,
+    /// Maximum distance of corresponding points for the initial alignment
+    /// of a loop closure, which has to cover the accumulated drift
+    pub loop_closure_correspondence_distance: Scalar,
+    /// Loop closures that match worse than this are rejected
+    pub loop_closure_min_fitness: Scalar,
+    pub loop_closure_max_rmse: Scalar,
+    /// Maximum number of Gauss-Newton iterations after a loop closure
+    pub max_iterations: usize,
+}
+
+impl Default for SlamConfig {
+    fn default() -> SlamConfig {
+        SlamConfig {
+            keyframe_distance: 0.5,
+            keyframe_angle: 0.5,
+            position_std_dev: 0.02,
+            heading_std_dev: 0.01,
+            odometry_scale: 10.0,
+            loop_closure_radius: 2.0,
+            loop_closure_gap: 20,
+            loop_closure_correspondence_distance: 0.6,
+            loop_closure_min_fitness: 0.85,
+            loop_closure_max_rmse: 0.1,
+            max_iterations: 10,
+        }
+    }
+}
+
+/// Scan of a keyframe, and the same scan prepared for matching.
+struct Keyframe {
+    scan: Scan,
+    reference: Reference,
+}
+
+/// Builds a grid map from laser scans, like the `Mapper`, but corrects the
+/// trajectory whenever the robot returns to a place it has seen before. The
+/// map is then rendered again from the keyframes at their corrected poses.
+pub struct GraphSlam {
+    pub config: SlamConfig,
+    pub icp: IcpConfig,
+    pub graph: PoseGraph,
+    pub gridmap: GridMap,
+    pub drive_geometry: DriveGeometry,
+    /// Number of loop closures so far
+    pub loop_closures: usize,
+    /// Estimated pose, relative to the pose at which the SLAM started
+    pose_estimate: Pose,
+    /// Tracks the pose between keyframes by matching consecutive scans
+    tracking: ScanToScan,
+    keyframes: Vec<Keyframe>,
+}
+
+impl Default for GraphSlam {
+    fn default() -> GraphSlam {
+        GraphSlam::new(SlamConfig::default(), IcpConfig::default())
+    }
+}
+
+impl GraphSlam {
+    pub fn new(config: SlamConfig, icp: IcpConfig) -> GraphSlam {
+        // Matches that slide along corridors would corrupt the pose graph
+        let mut tracking = ScanToScan::new(icp, 0.7);
+        tracking.max_translation_correction = 0.1;
+        tracking.max_rotation_correction = 0.1;
+
+        GraphSlam {
+            config,
+            icp,
+            graph: PoseGraph::new(),
+            gridmap: GridMap::default(),
+            drive_geometry: DriveGeometry::default(),
+            loop_closures: 0,
+            pose_estimate: Pose::default(),
+            tracking,
+            keyframes: vec![],
+        }
+    }
+
+    pub fn pose_estimate(&self) -> Pose {
+        self.pose_estimate.clone()
+    }
+
+    pub fn update(&mut self, readings: &SensorReadings) {
+        self.pose_estimate = self
+            .drive_geometry
+            .to_motion(&readings.odometry)
+            .apply(&self.pose_estimate);
+
+        if let Some(ref scan) = readings.scan {
+            let cloud = scan.to_pointcloud(&Pose::default());
+
+            if let Some((pose, _)) = self.tracking.measure(&cloud, &self.pose_estimate) {
+                self.pose_estimate = pose;
+            }
+            self.tracking
+                .set_reference(&cloud, self.pose_estimate.clone());
+
+            if self.needs_keyframe() {
+                self.add_keyframe(scan, &cloud);
+            }
+        }
+    }
+
+    fn needs_keyframe(&self) -> bool {
+        match self.graph.nodes.last() {
+            None => true,
+            Some(last) => {
+                let relative = last.inverse().compose(&self.pose_estimate);
+                relative.position.length() >= self.config.keyframe_distance
+                    || normalize_angle(relative.heading).abs() >= self.config.keyframe_angle
+            }
+        }
+    }
+
+    fn add_keyframe(&mut self, scan: &Scan, cloud: &PointCloud) {
+        let index = self.graph.add_node(self.pose_estimate.clone());
+        self.keyframes.push(Keyframe {
+            scan: scan.clone(),
+            reference: Reference::new(cloud, &self.icp),
+        });
+
+        if index > 0 {
+            let guess = self.graph.nodes[index - 1]
+                .inverse()
+                .compose(&self.pose_estimate);
+            let reference = &self.keyframes[index - 1].reference;
+
+            let (measurement, information) =
+                match scanmatch::icp(reference, cloud, &guess, &self.icp) {
+                    Some(ref result) if result.fitness >= self.tracking.min_fitness => {
+                        (result.pose.clone(), self.information(result))
+                    }
+                    _ => {
+                        let scale = self.config.odometry_scale;
+                        (guess, self.information_scaled(scale))
+                    }
+                };
+            self.graph
+                .add_edge(index - 1, index, measurement, information);
+        }
+
+        if self.close_loop(index, cloud) {
+            self.loop_closures += 1;
+            self.graph.optimize(self.config.max_iterations);
+
+            self.pose_estimate = self.graph.nodes[index].clone();
+            self.tracking
+                .set_reference(cloud, self.pose_estimate.clone());
+            self.render_map();
+        } else {
+            self.gridmap.update(&self.pose_estimate, scan);
+        }
+    }
+
+    /// Try to match the keyframe with the given index against older, nearby
+    /// keyframes. Adds an edge for the closest match and returns whether
+    /// there was one.
+    fn close_loop(&mut self, index: usize, cloud: &PointCloud) -> bool {
+        let pose = &self.graph.nodes[index];
+        let mut candidates: Vec<(usize, Scalar)> = self.graph.nodes
+            [..index.saturating_sub(self.config.loop_closure_gap)]
+            .iter()
+            .enumerate()
+            .map(|(j, other)| (j, (other.position - pose.position).length()))
+            .filter(|&(_, distance)| distance < self.config.loop_closure_radius)
+            .collect();
+        candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        // A coarse alignment to cover the drift, followed by a precise one
+        let coarse = IcpConfig {
+            max_correspondence_distance: self.config.loop_closure_correspondence_distance,
+            ..self.icp
+        };
+
+        for &(j, _) in candidates.iter().take(3) {
+            let reference = &self.keyframes[j].reference;
+            let guess = self.graph.nodes[j].inverse().compose(pose);
+
+            let result = scanmatch::icp(reference, cloud, &guess, &coarse)
+                .and_then(|coarse| scanmatch::icp(reference, cloud, &coarse.pose, &self.icp));
+
+            if let Some(result) = result {
+                if result.fitness >= self.config.loop_closure_min_fitness
+                    && result.rmse <= self.config.loop_closure_max_rmse
+                {
+                    let information = self.information(&result);
+                    self.graph.add_edge(j, index, result.pose, information);
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Information of a scan match. Matches that do not fit well are
+    /// trusted less.
+    fn information(&self, result: &MatchResult) -> Matrix3 {
+        self.information_scaled(result.uncertainty_scale())
+    }
+
+    fn information_scaled(&self, scale: Scalar) -> Matrix3 {
+        let position = (scale * self.config.position_std_dev).powi(2);
+        let heading = (scale * self.config.heading_std_dev).powi(2);
+        Matrix3::diagonal([1.0 / position, 1.0 / position, 1.0 / heading])
+    }
+
+    /// Build the grid map from scratch, from the scans of all keyframes.
+    fn render_map(&mut self) {
+        self.gridmap.clear();
+        for (keyframe, pose) in self.keyframes.iter().zip(&self.graph.nodes) {
+            self.gridmap.update(pose, &keyframe.scan);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use geometry::{Pose, Vector};
+use math::{normalize_angle, Matrix3, Scalar};
+
+/// A measurement of the pose of node `to`, relative to node `from`.
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub measurement: Pose,
+    /// Inverse covariance of the measurement (x, y, heading)
+    pub information: Matrix3,
+}
+
+/// Graph of robot poses (nodes) and relative measurements between them
+/// (edges). The poses are estimated by nonlinear least squares, see
+/// "A Tutorial on Graph-Based SLAM" by Grisetti, Kümmerle, Stachniss and
+/// Burgard.
+#[derive(Default)]
+pub struct PoseGraph {
+    pub nodes: Vec<Pose>,
+    pub edges: Vec<Edge>,
+}
+
+impl PoseGraph {
+    pub fn new() -> PoseGraph {
+        PoseGraph::default()
+    }
+
+    /// Add a node and return its index.
+    pub fn add_node(&mut self, pose: Pose) -> usize {
+        self.nodes.push(pose);
+        self.nodes.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, measurement: Pose, information: Matrix3) {
+        self.edges.push(Edge {
+            from,
+            to,
+            measurement,
+            information,
+        });
+    }
+
+    /// Sum of the squared errors of all edges, weighted with their
+    /// information.
+    pub fn error(&self) -> Scalar {
+        self.edges
+            .iter()
+            .map(|edge| {
+                let e = edge_error(&self.nodes[edge.from], &self.nodes[edge.to], edge);
+                dot(e, edge.information.mul_vector(e))
+            })
+            .sum()
+    }
+
+    /// Optimize the poses with Gauss-Newton. The first node is kept fixed.
+    /// Returns the number of iterations.
+    pub fn optimize(&mut self, max_iterations: usize) -> usize {
+        if self.nodes.len() < 2 {
+            return 0;
+        }
+
+        for iteration in 1..=max_iterations {
+            let mut h = BlockMatrix {
+                diagonal: vec![Matrix3::zero(); self.nodes.len()],
+                off_diagonal: Vec::with_capacity(self.edges.len()),
+            };
+            let mut b = vec![[0.0; 3]; self.nodes.len()];
+
+            for edge in &self.edges {
+                let (xi, xj) = (&self.nodes[edge.from], &self.nodes[edge.to]);
+                let e = edge_error(xi, xj, edge);
+                let (a, bj) = edge_jacobians(xi, xj, edge);
+
+                let a_omega = a.transpose() * edge.information;
+                let b_omega = bj.transpose() * edge.information;
+
+                h.diagonal[edge.from] = h.diagonal[edge.from] + a_omega * a;
+                h.diagonal[edge.to] = h.diagonal[edge.to] + b_omega * bj;
+                h.off_diagonal.push((edge.from, edge.to, a_omega * bj));
+
+                add(&mut b[edge.from], a_omega.mul_vector(e), -1.0);
+                add(&mut b[edge.to], b_omega.mul_vector(e), -1.0);
+            }
+
+            let delta = match solve_pcg(&h, &b) {
+                Some(delta) => delta,
+                None => return iteration,
+            };
+
+            let mut max_step: Scalar = 0.0;
+            for (node, d) in self.nodes.iter_mut().zip(delta) {
+                node.position = node.position + Vector::new(d[0], d[1]);
+                node.heading = normalize_angle(node.heading + d[2]);
+                max_step = max_step.max(d[0].abs()).max(d[1].abs()).max(d[2].abs());
+            }
+
+            if max_step < 1e-4 {
+                return iteration;
+            }
+        }
+
+        max_iterations
+    }
+}
+
+/// Symmetric matrix of 3x3 blocks, one block row and column per node. Apart
+/// from the diagonal, only the blocks of nodes that share an edge are
+/// non-zero.
+struct BlockMatrix {
+    diagonal: Vec<Matrix3>,
+    /// Blocks `(i, j)`, one per edge. The block `(j, i)` is the transpose.
+    off_diagonal: Vec<(usize, usize, Matrix3)>,
+}
+
+impl BlockMatrix {
+    fn mul_vector(&self, x: &[[Scalar; 3]]) -> Vec<[Scalar; 3]> {
+        let mut y: Vec<[Scalar; 3]> = self
+            .diagonal
+            .iter()
+            .zip(x)
+            .map(|(block, &x)| block.mul_vector(x))
+            .collect();
+
+        for &(i, j, ref block) in &self.off_diagonal {
+            add(&mut y[i], block.mul_vector(x[j]), 1.0);
+            add(&mut y[j], block.transpose().mul_vector(x[i]), 1.0);
+        }
+
+        y
+    }
+}
+
+/// Solve `h * x = b` with the conjugate gradient method, preconditioned with
+/// the inverses of the diagonal blocks. The first node is fixed, its entries
+/// of `x` stay zero. Returns `None` if `h` is not positive definite.
+fn solve_pcg(h: &BlockMatrix, b: &[[Scalar; 3]]) -> Option<Vec<[Scalar; 3]>> {
+    let n = b.len();
+
+    let mut preconditioner = Vec::with_capacity(n);
+    preconditioner.push(Matrix3::zero());
+    for block in &h.diagonal[1..] {
+        preconditioner.push(block.inverse_spd()?);
+    }
+    let precondition = |r: &[[Scalar; 3]]| -> Vec<[Scalar; 3]> {
+        preconditioner
+            .iter()
+            .zip(r)
+            .map(|(m, &r)| m.mul_vector(r))
+            .collect()
+    };
+
+    let mut x = vec![[0.0; 3]; n];
+    let mut r = b.to_vec();
+    let mut z = precondition(&r);
+    let mut p = z.clone();
+    let mut rz = dot_all(&r, &z);
+    let tolerance = 1e-12 * rz;
+
+    // In exact arithmetic, the method converges after at most 3n iterations
+    for _ in 0..3 * n {
+        if rz <= tolerance {
+            break;
+        }
+
+        let hp = h.mul_vector(&p);
+        let php = dot_all(&p, &hp);
+        if php <= 0.0 || !php.is_finite() {
+            return None;
+        }
+
+        let alpha = rz / php;
+        for k in 0..n {
+            add(&mut x[k], p[k], alpha);
+            add(&mut r[k], hp[k], -alpha);
+        }
+
+        z = precondition(&r);
+        let rz_next = dot_all(&r, &z);
+        let beta = rz_next / rz;
+        for (p, z) in p.iter_mut().zip(&z) {
+            for (p, z) in p.iter_mut().zip(z) {
+                *p = z + beta * *p;
+            }
+        }
+        rz = rz_next;
+    }
+
+    Some(x)
+}
+
+/// `a += factor * b`
+fn add(a: &mut [Scalar; 3], b: [Scalar; 3], factor: Scalar) {
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a += factor * b;
+    }
+}
+
+fn dot_all(a: &[[Scalar; 3]], b: &[[Scalar; 3]]) -> Scalar {
+    a.iter().zip(b).map(|(&a, &b)| dot(a, b)).sum()
+}
+
+fn dot(a: [Scalar; 3], b: [Scalar; 3]) -> Scalar {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Difference between the measured and the current relative pose of an edge.
+fn edge_error(xi: &Pose, xj: &Pose, edge: &Edge) -> [Scalar; 3] {
+    let z = &edge.measurement;
+    let relative = (xj.position - xi.position).rotate(-xi.heading);
+    let d = (relative - z.position).rotate(-z.heading);
+
+    [
+        d.x,
+        d.y,
+        normalize_angle(xj.heading - xi.heading - z.heading),
+    ]
+}
+
+/// Derivatives of the edge error with respect to both poses.
+fn edge_jacobians(xi: &Pose, xj: &Pose, edge: &Edge) -> (Matrix3, Matrix3) {
+    let (si, ci) = xi.heading.sin_cos();
+    let (sz, cz) = edge.measurement.heading.sin_cos();
+    let t = xj.position - xi.position;
+
+    // Transposed rotation of the measurement, and of the first pose together
+    // with its derivative
+    let rz_t = Matrix3::new([[cz, sz, 0.0], [-sz, cz, 0.0], [0.0, 0.0, 1.0]]);
+    let ri_t = Matrix3::new([[ci, si, 0.0], [-si, ci, 0.0], [0.0, 0.0, 1.0]]);
+    let d_ri_t = [-si * t.x + ci * t.y, -ci * t.x - si * t.y];
+
+    let rotation = rz_t * ri_t;
+    let d_rotation = rz_t.mul_vector([d_ri_t[0], d_ri_t[1], 0.0]);
+
+    let mut a = rotation * -1.0;
+    a.m[0][2] = d_rotation[0];
+    a.m[1][2] = d_rotation[1];
+    a.m[2][2] = -1.0;
+
+    (a, rotation)
+}
@@ -0,0 +1,32 @@
+use geometry::Pose;
+use motion::Velocity;
+use sensor::SensorReadings;
+
+use super::slam::GraphSlam;
+use super::wall_follower::WallSteering;
+use super::{Controller, DebugState};
+
+/// Follows the walls, and builds a map with graph-based SLAM.
+#[derive(Default)]
+pub struct SlamMapper {
+    pub slam: GraphSlam,
+    driver: WallSteering,
+}
+
+impl Controller for SlamMapper {
+    fn cycle(&mut self, readings: &SensorReadings) -> Velocity {
+        self.slam.update(readings);
+        self.driver.steer(readings)
+    }
+
+    fn pose_estimate(&self) -> Pose {
+        self.slam.pose_estimate()
+    }
+
+    fn debug_state(&self) -> DebugState<'_> {
+        DebugState {
+            gridmap: Some(&self.slam.gridmap),
+            ..DebugState::default()
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use geometry::Pose;
+use math::{normalize_angle, Angle, Scalar, PI};
+use motion::Velocity;
+use sensor::laserscanner::Scan;
+use sensor::SensorReadings;
+
+use super::mapper::Mapper;
+use super::{Controller, DebugState, SPEED, TURN_RATE};
+
+/// Follows the wall on the right-hand side of the robot, and builds a map.
+#[derive(Default)]
+pub struct WallFollower {
+    pub mapper: Mapper,
+    pub steering: WallSteering,
+}
+
+impl Controller for WallFollower {
+    fn cycle(&mut self, readings: &SensorReadings) -> Velocity {
+        self.mapper.update(readings);
+        self.steering.steer(readings)
+    }
+
+    fn pose_estimate(&self) -> Pose {
+        self.mapper.pose_estimate.clone()
+    }
+
+    fn debug_state(&self) -> DebugState<'_> {
+        DebugState {
+            gridmap: Some(&self.mapper.gridmap),
+            ..DebugState::default()
+        }
+    }
+}
+
+/// Steers along the wall on the right-hand side of the robot, using the laser
+/// scanner to keep a constant distance (PD control on the curvature). This is
+/// used on its own by controllers that do their own pose estimation.
+pub struct WallSteering {
+    /// Forward speed in m/s
+    pub speed: Scalar,
+    /// Desired distance between the robot center and the wall
+    pub wall_distance: Scalar,
+    /// Proportional gain (curvature per meter of distance error)
+    pub kp: Scalar,
+    /// Derivative gain (curvature per unit of distance change along the path)
+    pub kd: Scalar,
+    pub max_curvature: Scalar,
+    /// Rate at which to turn in place when there is a wall ahead, in rad/s
+    pub turn_rate: Angle,
+    /// Distance to the wall and time of the previous scan
+    last_wall_distance: Option<(Scalar, Scalar)>,
+    /// Command of the previous cycle, kept until a new scan arrives
+    last_command: Velocity,
+}
+
+impl Default for WallSteering {
+    fn default() -> WallSteering {
+        WallSteering {
+            speed: SPEED,
+            wall_distance: 0.4,
+            kp: 4.0,
+            kd: 3.2,
+            max_curvature: 3.0,
+            turn_rate: TURN_RATE,
+            last_wall_distance: None,
+            last_command: Velocity::stop(),
+        }
+    }
+}
+
+impl WallSteering {
+    /// Velocity for the current cycle.
+    pub fn steer(&mut self, readings: &SensorReadings) -> Velocity {
+        if readings.bumper.any() {
+            self.last_wall_distance = None;
+            self.last_command = Velocity::new(0.0, self.turn_rate);
+            return self.last_command;
+        }
+
+        let scan = match readings.scan {
+            Some(ref scan) => scan,
+            None => return self.last_command,
+        };
+
+        let front = closest_in_sector(scan, 0.0, PI / 6.0);
+        let right = closest_in_sector(scan, -PI / 2.0, PI / 6.0);
+
+        if front.unwrap_or(Scalar::INFINITY) < self.wall_distance {
+            self.last_wall_distance = None;
+            self.last_command = Velocity::new(0.0, self.turn_rate);
+            return self.last_command;
+        }
+
+        // Without a wall on the right, this makes the robot turn right to find one
+        let right = right
+            .unwrap_or(Scalar::INFINITY)
+            .min(2.0 * self.wall_distance);
+
+        let error = right - self.wall_distance;
+        let derivative = self.last_wall_distance.map_or(0.0, |(last, time)| {
+            let travelled = self.speed * (readings.time - time);
+            if travelled > 0.0 {
+                (right - last) / travelled
+            } else {
+                0.0
+            }
+        });
+        self.last_wall_distance = Some((right, readings.time));
+
+        let curvature = -(self.kp * error + self.kd * derivative)
+            .clamp(-self.max_curvature, self.max_curvature);
+
+        self.last_command = Velocity::new(self.speed, curvature * self.speed);
+        self.last_command
+    }
+}
+
+/// Shortest measured distance within `half_width` around the given direction.
+fn closest_in_sector(scan: &Scan, direction: Angle, half_width: Angle) -> Option<Scalar> {
+    scan.hits()
+        .filter(|m| normalize_angle(m.angle - direction).abs() <= half_width)
+        .map(|m| m.distance)
+        .fold(None, |closest, d| match closest {
+            Some(c) if c <= d => Some(c),
+            _ => Some(d),
+        })
+}
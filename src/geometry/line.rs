@@ -1,4 +1,5 @@
 use super::{Point, Ray, Target, Vector};
+use math::Scalar;
 
 pub struct Line {
     pub start: Vector,
@@ -9,6 +10,24 @@ impl Line {
     pub fn new(start: Vector, end: Vector) -> Line {
         Line { start, end }
     }
+
+    /// The point on this line segment which is closest to `p`.
+    pub fn closest_point(&self, p: Vector) -> Vector {
+        let s = self.end - self.start;
+        let len2 = s.dot(s);
+
+        if len2 == 0.0 {
+            return self.start;
+        }
+
+        let u = ((p - self.start).dot(s) / len2).clamp(0.0, 1.0);
+        self.start + s * u
+    }
+
+    /// Euclidean distance between `p` and this line segment.
+    pub fn distance(&self, p: Vector) -> Scalar {
+        (p - self.closest_point(p)).length()
+    }
 }
 
 impl Target for Line {
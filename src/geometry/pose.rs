@@ -17,4 +17,19 @@ impl Pose {
     pub fn new(position: Vector, heading: Angle) -> Pose {
         Pose { position, heading }
     }
+
+    /// Transform a vector from the local frame of this pose to the global frame.
+    pub fn transform(&self, v: Vector) -> Vector {
+        self.position + v.rotate(self.heading)
+    }
+
+    /// Concatenate a pose that is given relative to this one.
+    pub fn compose(&self, other: &Pose) -> Pose {
+        Pose::new(self.transform(other.position), self.heading + other.heading)
+    }
+
+    /// The pose of the global origin, as seen from this pose.
+    pub fn inverse(&self) -> Pose {
+        Pose::new((-self.position).rotate(-self.heading), -self.heading)
+    }
 }
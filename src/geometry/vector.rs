@@ -40,6 +40,14 @@ impl ops::Mul<Scalar> for Vector {
     }
 }
 
+impl ops::Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector::new(-self.x, -self.y)
+    }
+}
+
 impl cmp::PartialEq for Vector {
     fn eq(&self, other: &Vector) -> bool {
         self.x == other.x && self.y == other.y
@@ -0,0 +1,321 @@
+use graphics::{self, Transformed};
+use opengl_graphics::{GlGraphics, OpenGL};
+use piston_window::*;
+
+use vacuum_robot_simulator::geometry::{Pose, Vector};
+use vacuum_robot_simulator::motion::Velocity;
+use vacuum_robot_simulator::render::{Draw, EstimateOverlay, RenderConfig};
+use vacuum_robot_simulator::simulation::clock::{Clock, ClockMode};
+use vacuum_robot_simulator::simulation::Simulation;
+
+/// Manual control of the robot with the keyboard.
+#[derive(Default)]
+struct Teleop {
+    enabled: bool,
+    forward: bool,
+    backward: bool,
+    left: bool,
+    right: bool,
+}
+
+impl Teleop {
+    /// Forward speed in m/s
+    const SPEED: f64 = 0.5;
+    /// Turn rate in rad/s
+    const TURN_RATE: f64 = 2.0;
+
+    /// Update the state of the drive keys. Returns false for other keys.
+    fn set_key(&mut self, key: Key, pressed: bool) -> bool {
+        match key {
+            Key::Up | Key::W => self.forward = pressed,
+            Key::Down | Key::S => self.backward = pressed,
+            Key::Left | Key::A => self.left = pressed,
+            Key::Right | Key::D => self.right = pressed,
+            _ => return false,
+        }
+        true
+    }
+
+    fn velocity(&self) -> Option<Velocity> {
+        if !self.enabled {
+            return None;
+        }
+
+        let axis = |positive: bool, negative: bool| match (positive, negative) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        };
+
+        Some(Velocity::new(
+            Teleop::SPEED * axis(self.forward, self.backward),
+            Teleop::TURN_RATE * axis(self.left, self.right),
+        ))
+    }
+}
+
+struct App {
+    gl: GlGraphics,
+    render_config: RenderConfig,
+    simulation: Simulation,
+    clock: Clock,
+    teleop: Teleop,
+    /// Size of the window, in pixels
+    size: [f64; 2],
+    /// Last known position of the mouse cursor, in window coordinates
+    cursor: [f64; 2],
+    /// Position at which the robot was placed with the mouse, while the
+    /// button is held down
+    placing: Option<Vector>,
+    /// Whether the map is being dragged with the mouse
+    panning: bool,
+    /// Keep the robot in the center of the window
+    follow: bool,
+    /// Show the pose estimate of the controller
+    show_estimate: bool,
+    /// Show the particles of a particle filter
+    show_particles: bool,
+}
+
+const COLOR_BG: [f32; 4] = [0.17, 0.35, 0.62, 1.0];
+
+impl App {
+    fn render(&mut self, args: &RenderArgs) {
+        self.size = [f64::from(args.width), f64::from(args.height)];
+        let (x, y) = (self.size[0] / 2.0, self.size[1] / 2.0);
+
+        if self.follow {
+            self.render_config.offset = self.simulation.robot.pose.position;
+        }
+
+        // Clear screen
+        graphics::clear(COLOR_BG, &mut self.gl);
+
+        let render_config = &self.render_config;
+
+        let objects = &self.simulation.objects;
+        let robot = &self.simulation.robot;
+        let pointcloud = self.simulation.last_scan.to_pointcloud(&robot.pose);
+        let controller = &self.simulation.controller;
+        let controller_state = controller.debug_state();
+
+        let estimate = if self.show_estimate {
+            Some(EstimateOverlay::new(
+                &controller.pose_estimate(),
+                &controller_state,
+                self.simulation.evaluation.origin(),
+                self.show_particles,
+            ))
+        } else {
+            None
+        };
+
+        self.gl.draw(args.viewport(), |c, gl| {
+            let transform = c.transform.trans(x, y);
+
+            // Draw all static objects
+            for o in objects {
+                o.draw(render_config, transform, gl);
+            }
+
+            // Draw robot
+            robot.draw(render_config, transform, gl);
+
+            // Draw current LiDAR measurements
+            pointcloud.draw(render_config, transform, gl);
+
+            // Draw the internal state of the controller
+            controller_state.draw(render_config, transform, gl);
+
+            // Draw the pose estimate next to the true pose
+            if let Some(ref estimate) = estimate {
+                estimate.draw(render_config, transform, gl);
+            }
+        });
+    }
+
+    fn update(&mut self, args: &UpdateArgs) {
+        for _ in 0..self.clock.advance(args.dt) {
+            self.simulation.step();
+        }
+    }
+
+    fn key_pressed(&mut self, key: Key) {
+        if self.teleop.set_key(key, true) {
+            self.simulation.teleop = self.teleop.velocity();
+            return;
+        }
+
+        match key {
+            Key::Space => {
+                self.clock.mode = match self.clock.mode {
+                    ClockMode::Paused => ClockMode::RealTime,
+                    _ => ClockMode::Paused,
+                }
+            }
+            Key::F => {
+                self.clock.mode = match self.clock.mode {
+                    ClockMode::FastForward(_) => ClockMode::RealTime,
+                    _ => ClockMode::FastForward(5.0),
+                }
+            }
+            Key::C => self.follow = !self.follow,
+            Key::E => self.show_estimate = !self.show_estimate,
+            Key::P => self.show_particles = !self.show_particles,
+            Key::Home => self.fit_map(),
+            Key::T => {
+                self.teleop.enabled = !self.teleop.enabled;
+                self.simulation.teleop = self.teleop.velocity();
+            }
+            _ => {}
+        }
+    }
+
+    /// Mouse cursor position, relative to the center of the window.
+    fn cursor_pixel_coords(&self) -> (f64, f64) {
+        (
+            self.cursor[0] - self.size[0] / 2.0,
+            self.cursor[1] - self.size[1] / 2.0,
+        )
+    }
+
+    /// World coordinates of the mouse cursor.
+    fn cursor_position(&self) -> Vector {
+        let (x, y) = self.cursor_pixel_coords();
+        self.render_config.world_coords(x, y)
+    }
+
+    /// Zoom in or out, around the mouse cursor.
+    fn zoom(&mut self, amount: f64) {
+        let (x, y) = self.cursor_pixel_coords();
+        self.render_config.zoom_at(1.0 + 0.2 * amount, x, y);
+    }
+
+    /// Show the whole map.
+    fn fit_map(&mut self) {
+        let mut min = Vector::new(f64::INFINITY, f64::INFINITY);
+        let mut max = Vector::new(f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for o in &self.simulation.objects {
+            for p in &[o.start, o.end] {
+                min = Vector::new(min.x.min(p.x), min.y.min(p.y));
+                max = Vector::new(max.x.max(p.x), max.y.max(p.y));
+            }
+        }
+
+        if min.x <= max.x {
+            self.follow = false;
+            self.render_config.fit(min, max, self.size[0], self.size[1]);
+        }
+    }
+
+    /// Teleport the robot to the clicked position, keeping its heading.
+    fn start_placing(&mut self) {
+        let position = self.cursor_position();
+        let heading = self.simulation.robot.pose.heading;
+        self.simulation.robot.teleport(Pose::new(position, heading));
+        self.placing = Some(position);
+    }
+
+    /// Point the robot towards the mouse cursor or pan the map while
+    /// dragging.
+    fn mouse_moved(&mut self, cursor: [f64; 2]) {
+        if self.panning {
+            self.follow = false;
+            self.render_config
+                .pan(cursor[0] - self.cursor[0], cursor[1] - self.cursor[1]);
+        }
+        self.cursor = cursor;
+
+        if let Some(position) = self.placing {
+            let direction = self.cursor_position() - position;
+            if direction.length() > 0.1 * self.simulation.robot.radius {
+                let pose = Pose::new(position, direction.angle());
+                self.simulation.robot.teleport(pose);
+            }
+        }
+    }
+
+    fn key_released(&mut self, key: Key) {
+        if self.teleop.set_key(key, false) {
+            self.simulation.teleop = self.teleop.velocity();
+        }
+    }
+}
+
+/// Run the simulation in a window until it is closed.
+pub fn run(simulation: Simulation) -> Simulation {
+    let opengl = OpenGL::V3_2;
+
+    let size = [800, 400];
+    let mut window: PistonWindow = WindowSettings::new("Vacuum Robot Simulator", size)
+        .opengl(opengl)
+        .samples(4)
+        .exit_on_esc(true)
+        .build()
+        .unwrap();
+
+    let mut app = App {
+        gl: GlGraphics::new(opengl),
+        render_config: RenderConfig::new(20.0),
+        clock: Clock::new(simulation.timestep),
+        simulation,
+        teleop: Teleop::default(),
+        size: [f64::from(size[0]), f64::from(size[1])],
+        cursor: [0.0, 0.0],
+        placing: None,
+        panning: false,
+        follow: false,
+        show_estimate: true,
+        show_particles: true,
+    };
+    app.fit_map();
+
+    // The simulation runs with its own fixed timestep, independent of the
+    // update rate of the window
+    let mut events = Events::new(EventSettings::new().ups(60).max_fps(60));
+    while let Some(e) = events.next(&mut window) {
+        if let Some(a) = e.render_args() {
+            app.render(&a);
+        }
+
+        if let Some(a) = e.update_args() {
+            app.update(&a);
+        }
+
+        if let Some(Button::Keyboard(key)) = e.press_args() {
+            app.key_pressed(key);
+        }
+
+        if let Some(Button::Keyboard(key)) = e.release_args() {
+            app.key_released(key);
+        }
+
+        if let Some(a) = e.mouse_cursor_args() {
+            app.mouse_moved(a);
+        }
+
+        if let Some(Button::Mouse(MouseButton::Left)) = e.press_args() {
+            app.start_placing();
+        }
+
+        if let Some(Button::Mouse(MouseButton::Left)) = e.release_args() {
+            app.placing = None;
+        }
+
+        if let Some(Button::Mouse(MouseButton::Right)) = e.press_args() {
+            app.panning = true;
+        }
+
+        if let Some(Button::Mouse(MouseButton::Right)) = e.release_args() {
+            app.panning = false;
+        }
+
+        if let Some(a) = e.mouse_scroll_args() {
+            app.zoom(a[1]);
+        }
+    }
+
+    println!("Pose estimate: {}", app.simulation.evaluation);
+
+    app.simulation
+}
@@ -0,0 +1,79 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use vacuum_robot_simulator::simulation::Simulation;
+
+/// Run the simulation without a window for the given number of ticks, or
+/// until the controller has finished. If an output path is given, the true
+/// and the estimated trajectory are written to that file (CSV).
+pub fn run(simulation: &mut Simulation, ticks: u64, output: Option<&str>) -> io::Result<()> {
+    let mut writer = match output {
+        Some(path) => Some(BufWriter::new(File::create(path)?)),
+        None => None,
+    };
+
+    if let Some(ref mut w) = writer {
+        writeln!(
+            w,
+            "tick,time,x,y,heading,x_estimate,y_estimate,heading_estimate,\
+             x_std_dev,y_std_dev,heading_std_dev,bumper"
+        )?;
+    }
+
+    for _ in 0..ticks {
+        if simulation.controller.is_finished() {
+            println!("Controller finished");
+            break;
+        }
+
+        simulation.step();
+
+        if let Some(ref mut w) = writer {
+            let truth = &simulation.robot.pose;
+            let estimate = simulation
+                .evaluation
+                .to_world(&simulation.controller.pose_estimate());
+
+            // Uncertainty of the estimate (in the frame of the controller),
+            // if the controller provides one
+            let std_devs = match simulation.controller.debug_state().covariance {
+                Some(cov) => format!(
+                    "{:.4},{:.4},{:.4}",
+                    cov.m[0][0].sqrt(),
+                    cov.m[1][1].sqrt(),
+                    cov.m[2][2].sqrt()
+                ),
+                None => String::from(",,"),
+            };
+
+            writeln!(
+                w,
+                "{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{},{}",
+                simulation.ticks(),
+                simulation.time(),
+                truth.position.x,
+                truth.position.y,
+                truth.heading,
+                estimate.position.x,
+                estimate.position.y,
+                estimate.heading,
+                std_devs,
+                simulation.readings.bumper.any() as u8
+            )?;
+        }
+    }
+
+    let pose = &simulation.robot.pose;
+    println!(
+        "Simulated {} ticks ({:.1} s)",
+        simulation.ticks(),
+        simulation.time()
+    );
+    println!(
+        "Final pose: x = {:.3} m, y = {:.3} m, heading = {:.3} rad",
+        pose.position.x, pose.position.y, pose.heading
+    );
+    println!("Pose estimate: {}", simulation.evaluation);
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+#[cfg(feature = "gui")]
+extern crate graphics;
+#[cfg(feature = "gui")]
+extern crate opengl_graphics;
+extern crate rand;
+extern crate svg2polylines;
+
+pub mod controller;
+pub mod geometry;
+pub mod math;
+pub mod motion;
+pub mod pointcloud;
+#[cfg(feature = "gui")]
+pub mod render;
+pub mod scanmatch;
+pub mod sensor;
+pub mod simulation;
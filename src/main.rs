@@ -1,167 +1,176 @@
+#[cfg(feature = "gui")]
 extern crate graphics;
+#[cfg(feature = "gui")]
 extern crate opengl_graphics;
-extern crate piston;
+#[cfg(feature = "gui")]
 extern crate piston_window;
-extern crate svg2polylines;
+extern crate vacuum_robot_simulator;
 
 use std::env;
-use std::fs;
-use std::io::Read;
-
-use graphics::Transformed;
-use opengl_graphics::{GlGraphics, OpenGL};
-use piston_window::*;
-use svg2polylines::Polyline;
-
-pub mod controller;
-pub mod geometry;
-pub mod math;
-pub mod pointcloud;
-pub mod render;
-pub mod sensor;
-pub mod simulation;
-
-use controller::Controller;
-use geometry::{Line, Pose, Vector};
-use math::Scalar;
-use sensor::laserscanner::Scan;
-use simulation::robot::Robot;
-use simulation::sensor::laserscanner::LaserScanner;
-
-use render::{Draw, RenderConfig};
-
-struct App {
-    gl: GlGraphics,
-    render_config: RenderConfig,
-    robot: Robot,
-    last_scan: Scan,
-    controller: Controller,
-    objects: Vec<Line>,
+use std::path::Path;
+
+#[cfg(feature = "gui")]
+mod gui;
+mod headless;
+
+use vacuum_robot_simulator::controller;
+use vacuum_robot_simulator::controller::gridmap::pgm;
+use vacuum_robot_simulator::controller::Controller;
+use vacuum_robot_simulator::geometry::{Pose, Vector};
+use vacuum_robot_simulator::math::Scalar;
+use vacuum_robot_simulator::motion::MotionNoise;
+use vacuum_robot_simulator::simulation;
+use vacuum_robot_simulator::simulation::robot::Robot;
+use vacuum_robot_simulator::simulation::sensor::laserscanner::LaserScanner;
+use vacuum_robot_simulator::simulation::Simulation;
+
+struct Options {
+    map_path: String,
+    controller: String,
+    headless: bool,
+    ticks: Option<u64>,
+    seconds: Scalar,
+    output: Option<String>,
+    save_map: Option<String>,
+    load_map: Option<String>,
 }
 
-const COLOR_BG: [f32; 4] = [0.17, 0.35, 0.62, 1.0];
-
-impl App {
-    fn render(&mut self, args: &RenderArgs) {
-        let (x, y) = (f64::from(args.width / 2), f64::from(args.height / 2));
-
-        // Clear screen
-        graphics::clear(COLOR_BG, &mut self.gl);
-
-        let render_config = &self.render_config;
+fn usage(program: &str) -> ! {
+    println!(
+        "Usage: {} [--controller <name>] [--headless [--ticks <n> | --seconds <s>] [--output <file.csv>]] [--load-map <map.yaml>] [--save-map <map.yaml>] <map.svg>",
+        program
+    );
+    println!();
+    println!("Available controllers: {}", controller::NAMES.join(", "));
+    std::process::exit(1);
+}
 
-        let objects = &self.objects;
-        let robot = &self.robot;
-        let pointcloud = self.last_scan.to_pointcloud(&robot.pose);
-        let controller = &self.controller;
+fn parse_options() -> Options {
+    let mut args = env::args();
+    let program = args.next().unwrap_or_default();
+
+    let mut map_path = None;
+    let mut options = Options {
+        map_path: String::new(),
+        controller: String::from(controller::NAMES[0]),
+        headless: false,
+        ticks: None,
+        seconds: 60.0,
+        output: None,
+        save_map: None,
+        load_map: None,
+    };
 
-        self.gl.draw(args.viewport(), |c, gl| {
-            let transform = c.transform.trans(x, y);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage(&program));
 
-            // Draw all static objects
-            for o in objects {
-                o.draw(render_config, transform, gl);
+        match arg.as_str() {
+            "--controller" => options.controller = value(),
+            "--headless" => options.headless = true,
+            "--ticks" => {
+                options.ticks = Some(value().parse().unwrap_or_else(|_| usage(&program)));
             }
-
-            // Draw robot
-            robot.draw(render_config, transform, gl);
-
-            // Draw current LiDAR measurements
-            pointcloud.draw(render_config, transform, gl);
-
-            // Draw the internal state of the controller
-            controller.draw(render_config, transform, gl);
-        });
+            "--seconds" => {
+                options.ticks = None;
+                options.seconds = value().parse().unwrap_or_else(|_| usage(&program));
+            }
+            "--output" => options.output = Some(value()),
+            "--save-map" => options.save_map = Some(value()),
+            "--load-map" => options.load_map = Some(value()),
+            "-h" | "--help" => usage(&program),
+            _ if arg.starts_with('-') => usage(&program),
+            _ if map_path.is_none() => map_path = Some(arg),
+            _ => usage(&program),
+        }
     }
 
-    fn update(&mut self, _: &UpdateArgs) {
-        // Perform a laser scan
-        self.last_scan = self
-            .robot
-            .laser_scanner
-            .scan(&self.robot.pose, &self.objects);
-
-        // Run the perception algorithm
-        self.controller.cycle(&self.last_scan, &self.robot.pose);
-
-        // Move the robot (TODO)
-        self.robot.pose.position.y += 0.003;
-        // self.robot.pose.heading -= 0.0001;
-    }
+    options.map_path = map_path.unwrap_or_else(|| usage(&program));
+    options
 }
 
 fn main() {
-    let opengl = OpenGL::V3_2;
+    let options = parse_options();
 
-    let mut window: PistonWindow = WindowSettings::new("Vacuum Robot Simulator", [800, 400])
-        .opengl(opengl)
-        .samples(4)
-        .exit_on_esc(true)
-        .build()
-        .unwrap();
+    if !controller::NAMES.contains(&options.controller.as_str()) {
+        println!("Error: unknown controller '{}'", options.controller);
+        std::process::exit(1);
+    }
 
-    // TODO: check this
-    // window.set_ups(60);
-    // window.set_max_fps(60);
+    // Read static world from SVG file
+    let objects = simulation::map::load_svg(&options.map_path).unwrap_or_else(|e| {
+        println!("Error: {}", e);
+        std::process::exit(1);
+    });
 
     // Little helper to construct vectors
     let vec = |x, y| Vector::new(Scalar::from(x), Scalar::from(y));
 
-    let mut app = App {
-        gl: GlGraphics::new(opengl),
-        render_config: RenderConfig { scale: 20.0 },
-        last_scan: Scan::empty(),
-        robot: Robot {
-            pose: Pose::new(vec(1, 1), 0.2),
-            laser_scanner: LaserScanner { num_columns: 100 },
-        },
-        controller: Controller::default(),
-        objects: vec![],
-    };
+    let robot = Robot::new(
+        Pose::new(vec(1, 1), 0.2),
+        LaserScanner::new(100, 0),
+        MotionNoise::default(),
+        0,
+    );
+
+    // The map is known to the controller, relative to the start pose
+    let map = simulation::map::to_frame(&objects, &robot.pose);
+    let mut controller = controller::from_name(&options.controller, 0, &map).unwrap();
+    if let Some(ref path) = options.load_map {
+        load_map(controller.as_mut(), Path::new(&path));
+    }
 
-    // Read static world from SVG file
-    let args: Vec<_> = env::args().collect();
-    match args.len() {
-        2 => {}
-        _ => {
-            println!("Usage: {} <map.svg>", args[0]);
+    let mut simulation = Simulation::new(objects, robot, controller);
+
+    if options.headless {
+        let ticks = options
+            .ticks
+            .unwrap_or_else(|| (options.seconds / simulation.timestep).round() as u64);
+
+        headless::run(&mut simulation, ticks, options.output.as_deref()).unwrap_or_else(|e| {
+            println!("Error: {}", e);
             std::process::exit(1);
-        }
-    };
+        });
+    } else {
+        simulation = run_gui(simulation);
+    }
 
-    let mut file = fs::File::open(&args[1]).unwrap();
-    let mut s = String::new();
-    file.read_to_string(&mut s).unwrap();
+    if let Some(path) = options.save_map {
+        save_map(&simulation, Path::new(&path));
+    }
+}
 
-    // Parse data
-    let polylines: Vec<Polyline> = svg2polylines::parse(&s).unwrap_or_else(|e| {
+/// Give the controller a grid map from a file as its known map.
+fn load_map(controller: &mut dyn Controller, path: &Path) {
+    let gridmap = pgm::load(path).unwrap_or_else(|e| {
         println!("Error: {}", e);
         std::process::exit(1);
     });
-
-    let m_per_px = 0.02;
-    for polyline in &polylines {
-        for pair in polyline.windows(2) {
-            app.objects.push(Line::new(
-                Vector::new(pair[0].x * m_per_px, -pair[0].y * m_per_px),
-                Vector::new(pair[1].x * m_per_px, -pair[1].y * m_per_px),
-            ))
-        }
+    if !controller.set_map(gridmap) {
+        println!("Warning: the controller does not use a known map");
     }
+}
 
-    let mut events = Events::new(EventSettings::new());
-    while let Some(e) = events.next(&mut window) {
-        if let Some(a) = e.render_args() {
-            app.render(&a);
+/// Write the grid map that was built by the controller (if any).
+fn save_map(simulation: &Simulation, path: &Path) {
+    match simulation.controller.debug_state().gridmap {
+        Some(gridmap) => {
+            pgm::save(gridmap, path).unwrap_or_else(|e| {
+                println!("Error: {}: {}", path.display(), e);
+                std::process::exit(1);
+            });
+            println!("Saved map to {}", path.display());
         }
+        None => println!("Warning: the controller does not build a map"),
+    }
+}
 
-        if let Some(a) = e.update_args() {
-            app.update(&a);
-        }
+#[cfg(feature = "gui")]
+fn run_gui(simulation: Simulation) -> Simulation {
+    gui::run(simulation)
+}
 
-        if let Some(a) = e.mouse_scroll_args() {
-            app.render_config.scale *= 1.0 + 0.2 * a[1];
-            app.render_config.scale = f64::max(1.0, app.render_config.scale);
-        }
-    }
+#[cfg(not(feature = "gui"))]
+fn run_gui(_: Simulation) -> Simulation {
+    println!("Error: compiled without GUI support, use --headless");
+    std::process::exit(1);
 }
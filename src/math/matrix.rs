@@ -0,0 +1,235 @@
+use std::ops;
+
+use super::Scalar;
+
+/// A 3x3 matrix, e.g. for the covariance of a pose (x, y, heading).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix3 {
+    /// Entries, row by row
+    pub m: [[Scalar; 3]; 3],
+}
+
+impl Matrix3 {
+    pub fn new(m: [[Scalar; 3]; 3]) -> Matrix3 {
+        Matrix3 { m }
+    }
+
+    pub fn zero() -> Matrix3 {
+        Matrix3::new([[0.0; 3]; 3])
+    }
+
+    pub fn identity() -> Matrix3 {
+        Matrix3::diagonal([1.0, 1.0, 1.0])
+    }
+
+    pub fn diagonal(d: [Scalar; 3]) -> Matrix3 {
+        let mut result = Matrix3::zero();
+        for (i, &x) in d.iter().enumerate() {
+            result.m[i][i] = x;
+        }
+        result
+    }
+
+    /// The outer product `a * b^T`.
+    pub fn outer(a: [Scalar; 3], b: [Scalar; 3]) -> Matrix3 {
+        let mut result = Matrix3::zero();
+        for (row, &x) in result.m.iter_mut().zip(a.iter()) {
+            for (entry, &y) in row.iter_mut().zip(b.iter()) {
+                *entry = x * y;
+            }
+        }
+        result
+    }
+
+    pub fn transpose(&self) -> Matrix3 {
+        let mut result = Matrix3::zero();
+        for i in 0..3 {
+            for j in 0..3 {
+                result.m[i][j] = self.m[j][i];
+            }
+        }
+        result
+    }
+
+    pub fn determinant(&self) -> Scalar {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// The inverse matrix, or `None` if the matrix is (nearly) singular.
+    ///
+    /// The determinant is compared with the product of the row lengths, its
+    /// largest possible magnitude (Hadamard's inequality). This makes the
+    /// test independent of the scale of the entries.
+    pub fn inverse(&self) -> Option<Matrix3> {
+        let det = self.determinant();
+        let scale: Scalar = self
+            .m
+            .iter()
+            .map(|row| row.iter().map(|x| x * x).sum::<Scalar>().sqrt())
+            .product();
+        if scale == 0.0 || det.abs() <= 1e-12 * scale || !det.is_finite() {
+            return None;
+        }
+
+        let m = &self.m;
+        let cofactor = |r1: usize, r2: usize, c1: usize, c2: usize| {
+            m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]
+        };
+
+        Some(Matrix3::new([
+            [
+                cofactor(1, 2, 1, 2) / det,
+                -cofactor(0, 2, 1, 2) / det,
+                cofactor(0, 1, 1, 2) / det,
+            ],
+            [
+                -cofactor(1, 2, 0, 2) / det,
+                cofactor(0, 2, 0, 2) / det,
+                -cofactor(0, 1, 0, 2) / det,
+            ],
+            [
+                cofactor(1, 2, 0, 1) / det,
+                -cofactor(0, 2, 0, 1) / det,
+                cofactor(0, 1, 0, 1) / det,
+            ],
+        ]))
+    }
+
+    /// The inverse of a symmetric positive definite matrix (e.g. a
+    /// covariance), computed with the Cholesky decomposition. Returns `None`
+    /// if the matrix is not positive definite.
+    pub fn inverse_spd(&self) -> Option<Matrix3> {
+        // Decomposition self = L * L^T with a lower triangular L
+        let mut l = Matrix3::zero();
+        for j in 0..3 {
+            let d = self.m[j][j] - (0..j).map(|k| l.m[j][k].powi(2)).sum::<Scalar>();
+            if d <= 0.0 || !d.is_finite() {
+                return None;
+            }
+            l.m[j][j] = d.sqrt();
+            for i in j + 1..3 {
+                let sum: Scalar = (0..j).map(|k| l.m[i][k] * l.m[j][k]).sum();
+                l.m[i][j] = (self.m[i][j] - sum) / l.m[j][j];
+            }
+        }
+
+        // Invert L by forward substitution, then self^-1 = L^-T * L^-1
+        let mut l_inverse = Matrix3::zero();
+        for c in 0..3 {
+            for i in c..3 {
+                let rhs = if i == c { 1.0 } else { 0.0 };
+                let sum: Scalar = (c..i).map(|k| l.m[i][k] * l_inverse.m[k][c]).sum();
+                l_inverse.m[i][c] = (rhs - sum) / l.m[i][i];
+            }
+        }
+
+        Some(l_inverse.transpose() * l_inverse)
+    }
+
+    pub fn mul_vector(&self, v: [Scalar; 3]) -> [Scalar; 3] {
+        let mut result = [0.0; 3];
+        for (i, r) in result.iter_mut().enumerate() {
+            *r = (0..3).map(|j| self.m[i][j] * v[j]).sum();
+        }
+        result
+    }
+
+    /// Solve the linear system `self * x = b`, or `None` if the matrix is
+    /// (nearly) singular in the sense of `inverse`.
+    pub fn solve(&self, b: [Scalar; 3]) -> Option<[Scalar; 3]> {
+        self.inverse().map(|inverse| inverse.mul_vector(b))
+    }
+}
+
+impl ops::Add for Matrix3 {
+    type Output = Matrix3;
+
+    fn add(self, other: Matrix3) -> Matrix3 {
+        let mut result = self;
+        for i in 0..3 {
+            for j in 0..3 {
+                result.m[i][j] += other.m[i][j];
+            }
+        }
+        result
+    }
+}
+
+impl ops::Sub for Matrix3 {
+    type Output = Matrix3;
+
+    fn sub(self, other: Matrix3) -> Matrix3 {
+        self + other * -1.0
+    }
+}
+
+impl ops::Mul for Matrix3 {
+    type Output = Matrix3;
+
+    fn mul(self, other: Matrix3) -> Matrix3 {
+        let mut result = Matrix3::zero();
+        for i in 0..3 {
+            for j in 0..3 {
+                result.m[i][j] = (0..3).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        result
+    }
+}
+
+impl ops::Mul<Scalar> for Matrix3 {
+    type Output = Matrix3;
+
+    fn mul(self, s: Scalar) -> Matrix3 {
+        let mut result = self;
+        for row in result.m.iter_mut() {
+            for x in row.iter_mut() {
+                *x *= s;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse_of_small_covariance() {
+        let m = Matrix3::new([[2e-5, 1e-5, 0.0], [1e-5, 3e-5, 0.0], [0.0, 0.0, 4e-6]]);
+        let product = m * m.inverse().unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product.m[i][j] - expected).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_of_positive_definite_matrix() {
+        let m = Matrix3::new([[4e-6, 1e-6, 2e-7], [1e-6, 3e-6, 0.0], [2e-7, 0.0, 1e-7]]);
+        let product = m * m.inverse_spd().unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product.m[i][j] - expected).abs() < 1e-9);
+            }
+        }
+
+        let indefinite = Matrix3::diagonal([1.0, -1.0, 1.0]);
+        assert!(indefinite.inverse_spd().is_none());
+    }
+
+    #[test]
+    fn singular_matrix() {
+        let m = Matrix3::new([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 1.0]]);
+        assert!(m.inverse().is_none());
+        assert!(m.solve([1.0, 2.0, 3.0]).is_none());
+        assert!(Matrix3::zero().inverse().is_none());
+    }
+}
@@ -1,5 +1,12 @@
+pub mod matrix;
+
 use std::f64::consts;
 
+use rand::distributions::Normal;
+use rand::Rng;
+
+pub use self::matrix::Matrix3;
+
 /// Underlying numeric type.
 pub type Scalar = f64;
 
@@ -8,3 +15,24 @@ pub const PI: Scalar = consts::PI;
 
 /// Type synonym for angles.
 pub type Angle = Scalar;
+
+/// Wrap an angle to the interval (-PI, PI].
+pub fn normalize_angle(angle: Angle) -> Angle {
+    let a = angle % (2.0 * PI);
+    if a > PI {
+        a - 2.0 * PI
+    } else if a <= -PI {
+        a + 2.0 * PI
+    } else {
+        a
+    }
+}
+
+/// Draw a sample from a zero-mean normal distribution.
+pub fn sample_normal<R: Rng>(rng: &mut R, std_dev: Scalar) -> Scalar {
+    if std_dev > 0.0 {
+        rng.sample(Normal::new(0.0, std_dev))
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,135 @@
+//! Types for describing relative robot motions
+
+use rand::Rng;
+
+use geometry::{Pose, Vector};
+use math::{sample_normal, Angle, Scalar};
+
+/// A relative motion in the odometry representation: an initial rotation, a
+/// translation along the new heading and a final rotation.
+#[derive(Debug, Clone, Copy)]
+pub struct Motion {
+    pub rotation1: Angle,
+    pub translation: Scalar,
+    pub rotation2: Angle,
+}
+
+impl Motion {
+    pub fn new(rotation1: Angle, translation: Scalar, rotation2: Angle) -> Motion {
+        Motion {
+            rotation1,
+            translation,
+            rotation2,
+        }
+    }
+
+    /// A rotation in place.
+    pub fn rotation(angle: Angle) -> Motion {
+        Motion::new(angle, 0.0, 0.0)
+    }
+
+    /// A straight movement along the current heading.
+    pub fn translation(distance: Scalar) -> Motion {
+        Motion::new(0.0, distance, 0.0)
+    }
+
+    /// The pose that results from performing this motion, starting at `pose`.
+    pub fn apply(&self, pose: &Pose) -> Pose {
+        let heading = pose.heading + self.rotation1;
+        let position = pose.position + Vector::from_angle(heading) * self.translation;
+
+        Pose::new(position, heading + self.rotation2)
+    }
+}
+
+/// A velocity command for the differential drive.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Velocity {
+    /// Forward velocity in m/s
+    pub linear: Scalar,
+    /// Turn rate in rad/s (counterclockwise)
+    pub angular: Angle,
+}
+
+impl Velocity {
+    pub fn new(linear: Scalar, angular: Angle) -> Velocity {
+        Velocity { linear, angular }
+    }
+
+    pub fn stop() -> Velocity {
+        Velocity::default()
+    }
+
+    /// The motion that results from driving with this velocity for the given
+    /// duration (in seconds).
+    pub fn to_motion(&self, duration: Scalar) -> Motion {
+        let rotation = self.angular * duration;
+        Motion::new(rotation / 2.0, self.linear * duration, rotation / 2.0)
+    }
+
+    /// The velocity that is needed to perform the given motion in the given
+    /// duration (in seconds).
+    pub fn from_motion(motion: &Motion, duration: Scalar) -> Velocity {
+        Velocity::new(
+            motion.translation / duration,
+            (motion.rotation1 + motion.rotation2) / duration,
+        )
+    }
+}
+
+/// Noise parameters of the odometry motion model, see chapter 5.4 of
+/// "Probabilistic Robotics" by Thrun, Burgard and Fox. The parameters scale
+/// the variances of the errors, with the squared rotations and translation.
+#[derive(Debug, Clone, Copy)]
+pub struct MotionNoise {
+    /// Rotational error caused by rotation
+    pub alpha1: Scalar,
+    /// Rotational error caused by translation
+    pub alpha2: Scalar,
+    /// Translational error caused by translation
+    pub alpha3: Scalar,
+    /// Translational error caused by rotation
+    pub alpha4: Scalar,
+}
+
+impl Default for MotionNoise {
+    fn default() -> MotionNoise {
+        MotionNoise {
+            alpha1: 0.05,
+            alpha2: 0.01,
+            alpha3: 0.05,
+            alpha4: 0.01,
+        }
+    }
+}
+
+impl MotionNoise {
+    /// Perfect actuation, without any noise.
+    pub fn none() -> MotionNoise {
+        MotionNoise {
+            alpha1: 0.0,
+            alpha2: 0.0,
+            alpha3: 0.0,
+            alpha4: 0.0,
+        }
+    }
+
+    /// Draw a noisy version of the given motion. Each component gets a
+    /// normally distributed error, whose variance is a weighted sum of the
+    /// squared components.
+    pub fn sample<R: Rng>(&self, motion: &Motion, rng: &mut R) -> Motion {
+        let rot1 = motion.rotation1.powi(2);
+        let trans = motion.translation.powi(2);
+        let rot2 = motion.rotation2.powi(2);
+
+        let sd_rot1 = (self.alpha1 * rot1 + self.alpha2 * trans).sqrt();
+        let sd_trans = (self.alpha3 * trans + self.alpha4 * (rot1 + rot2)).sqrt();
+        let sd_rot2 = (self.alpha1 * rot2 + self.alpha2 * trans).sqrt();
+
+        Motion::new(
+            motion.rotation1 + sample_normal(rng, sd_rot1),
+            motion.translation + sample_normal(rng, sd_trans),
+            motion.rotation2 + sample_normal(rng, sd_rot2),
+        )
+    }
+}
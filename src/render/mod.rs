@@ -12,13 +12,56 @@ use geometry;
 use pointcloud;
 use simulation::robot;
 
+/// The camera: pixel coordinates are relative to the center of the window.
 pub struct RenderConfig {
+    /// Pixels per meter
     pub scale: f64,
+    /// World position that is shown in the center of the window
+    pub offset: geometry::Vector,
 }
 
 impl RenderConfig {
+    pub fn new(scale: f64) -> RenderConfig {
+        RenderConfig {
+            scale,
+            offset: geometry::Vector::new(0.0, 0.0),
+        }
+    }
+
     pub fn pixel_coords(&self, vec: geometry::Vector) -> (f64, f64) {
-        (self.scale * (vec.x as f64), -self.scale * (vec.y as f64))
+        let vec = vec - self.offset;
+        (self.scale * vec.x, -self.scale * vec.y)
+    }
+
+    /// Inverse of `pixel_coords`.
+    pub fn world_coords(&self, x: f64, y: f64) -> geometry::Vector {
+        self.offset + geometry::Vector::new(x / self.scale, -y / self.scale)
+    }
+
+    /// Move the camera by the given number of pixels.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.offset = self.offset - geometry::Vector::new(dx / self.scale, -dy / self.scale);
+    }
+
+    /// Change the scale by the given factor, keeping the world position under
+    /// the given pixel coordinates in place.
+    pub fn zoom_at(&mut self, factor: f64, x: f64, y: f64) {
+        let anchor = self.world_coords(x, y);
+        self.scale = f64::max(1.0, self.scale * factor);
+        self.offset = self.offset + anchor - self.world_coords(x, y);
+    }
+
+    /// Show the whole rectangle between `min` and `max` in a window of the
+    /// given size.
+    pub fn fit(&mut self, min: geometry::Vector, max: geometry::Vector, width: f64, height: f64) {
+        let size = max - min;
+        let margin = 0.9;
+
+        self.offset = (min + max) * 0.5;
+        self.scale = f64::max(
+            1.0,
+            margin * f64::min(width / size.x.max(1e-3), height / size.y.max(1e-3)),
+        );
     }
 }
 
@@ -40,33 +83,40 @@ impl Draw for geometry::Line {
     }
 }
 
-impl Draw for controller::Controller {
+impl<'a> Draw for controller::DebugState<'a> {
     fn draw(&self, config: &RenderConfig, transform: Matrix2d, gl: &mut GlGraphics) {
-        let transform_gridmap = transform.trans(-450.0, 0.0);
-        self.gridmap.draw(config, transform_gridmap, gl);
+        if let Some(gridmap) = self.gridmap {
+            let transform_gridmap = transform.trans(-450.0, 0.0);
+            gridmap.draw(config, transform_gridmap, gl);
+        }
     }
 }
 
 impl Draw for controller::gridmap::GridMap {
     fn draw(&self, config: &RenderConfig, transform: Matrix2d, gl: &mut GlGraphics) {
         use controller::gridmap::CellState::*;
-        let size = controller::gridmap::SIZE;
-        let cell_size = 0.1 * config.scale;
+        let cell_size = 0.4 * self.resolution() * config.scale;
 
         // Draw background
-        let rect_bg = Rectangle::new(color::hex("333333"));
-        let width = cell_size * (size as f64);
-        rect_bg.draw(
-            [0.0, -width, width, width],
-            &DrawState::default(),
-            transform,
-            gl,
-        );
+        if let Some((min, max)) = self.bounds() {
+            let rect_bg = Rectangle::new(color::hex("333333"));
+            rect_bg.draw(
+                [
+                    (min.0 as f64) * cell_size,
+                    -((max.1 + 1) as f64) * cell_size,
+                    ((max.0 - min.0 + 1) as f64) * cell_size,
+                    ((max.1 - min.1 + 1) as f64) * cell_size,
+                ],
+                &DrawState::default(),
+                transform,
+                gl,
+            );
+        }
 
         // Draw cells
-        let mut draw_cell = |rect: Rectangle, r, c| {
+        let mut draw_cell = |rect: Rectangle, (c, r): (i64, i64)| {
             let x = (c as f64) * cell_size;
-            let y = -(r as f64) * cell_size;
+            let y = -((r + 1) as f64) * cell_size;
 
             rect.draw(
                 [x, y, cell_size, cell_size],
@@ -78,13 +128,11 @@ impl Draw for controller::gridmap::GridMap {
 
         let rect_occupied = Rectangle::new(WHITE);
         let rect_freespace = Rectangle::new(color::hex("525f49"));
-        for r in 0..size {
-            for c in 0..size {
-                match self.cell_state(r, c) {
-                    Some(&Occupied(_)) => draw_cell(rect_occupied, r, c),
-                    Some(&Freespace) => draw_cell(rect_freespace, r, c),
-                    _ => {}
-                }
+        for (cell, log_odds) in self.cells() {
+            match self.classify(log_odds) {
+                Occupied => draw_cell(rect_occupied, cell),
+                Freespace => draw_cell(rect_freespace, cell),
+                Void => {}
             }
         }
     }
@@ -99,22 +147,21 @@ impl Draw for robot::Robot {
             resolution: 64,
         };
 
-        let robot_radius = config.scale;
+        let robot_diameter = 2.0 * self.radius * config.scale;
         let pos = self.pose.position;
         let (px, py) = config.pixel_coords(pos);
 
         robot_circ.draw(
-            [0.0, 0.0, robot_radius, robot_radius],
+            [0.0, 0.0, robot_diameter, robot_diameter],
             &Default::default(),
-            transform.trans(px - robot_radius / 2.0, py - robot_radius / 2.0),
+            transform.trans(px - robot_diameter / 2.0, py - robot_diameter / 2.0),
             gl,
         );
 
         // Draw heading angle
         let line = Line::new(robot_color, 1.0);
-        let (hx, hy) = config.pixel_coords(
-            pos + geometry::Vector::from_angle(self.pose.heading) * config.scale * 0.05,
-        );
+        let (hx, hy) = config
+            .pixel_coords(pos + geometry::Vector::from_angle(self.pose.heading) * self.radius);
         line.draw([px, py, hx, hy], &DrawState::default(), transform, gl);
     }
 }
@@ -139,3 +186,116 @@ impl Draw for pointcloud::PointCloud {
         }
     }
 }
+
+impl Draw for controller::planner::Path {
+    fn draw(&self, config: &RenderConfig, transform: Matrix2d, gl: &mut GlGraphics) {
+        let line = Line::new(color::hex("ffcc00"), 1.0);
+        for w in self.waypoints.windows(2) {
+            let (x1, y1) = config.pixel_coords(w[0]);
+            let (x2, y2) = config.pixel_coords(w[1]);
+            line.draw([x1, y1, x2, y2], &DrawState::default(), transform, gl);
+        }
+    }
+}
+
+/// The pose estimate of a controller (with its uncertainty), the particles
+/// of a particle filter and the planned path, in world coordinates.
+pub struct EstimateOverlay {
+    pub pose: geometry::Pose,
+    /// Covariance of the estimated position
+    pub position_covariance: Option<[[f64; 2]; 2]>,
+    pub particles: Vec<geometry::Pose>,
+    pub path: Option<controller::planner::Path>,
+}
+
+impl EstimateOverlay {
+    /// Transform the estimate of a controller into world coordinates, given
+    /// the start pose of the robot (`origin`).
+    pub fn new(
+        estimate: &geometry::Pose,
+        state: &controller::DebugState<'_>,
+        origin: &geometry::Pose,
+        with_particles: bool,
+    ) -> EstimateOverlay {
+        // Rotate the covariance of the position into the world frame
+        let (s, c) = origin.heading.sin_cos();
+        let position_covariance = state.covariance.map(|cov| {
+            let (xx, xy, yy) = (cov.m[0][0], cov.m[0][1], cov.m[1][1]);
+            [
+                [
+                    c * c * xx - 2.0 * c * s * xy + s * s * yy,
+                    c * s * (xx - yy) + (c * c - s * s) * xy,
+                ],
+                [
+                    c * s * (xx - yy) + (c * c - s * s) * xy,
+                    s * s * xx + 2.0 * c * s * xy + c * c * yy,
+                ],
+            ]
+        });
+
+        let particles = match state.particles {
+            Some(particles) if with_particles => {
+                particles.iter().map(|p| origin.compose(&p.pose)).collect()
+            }
+            _ => vec![],
+        };
+
+        EstimateOverlay {
+            pose: origin.compose(estimate),
+            position_covariance,
+            particles,
+            path: state.path.map(|path| path.transform(origin)),
+        }
+    }
+}
+
+impl Draw for EstimateOverlay {
+    fn draw(&self, config: &RenderConfig, transform: Matrix2d, gl: &mut GlGraphics) {
+        let arrow = |pose: &geometry::Pose, length: f64| {
+            let (x1, y1) = config.pixel_coords(pose.position);
+            let tip = pose.position + geometry::Vector::from_angle(pose.heading) * length;
+            let (x2, y2) = config.pixel_coords(tip);
+            [x1, y1, x2, y2]
+        };
+
+        // Draw particles as short arrows
+        let line = Line::new(color::hex("ff7f7fa0"), 0.5);
+        for pose in &self.particles {
+            line.draw(arrow(pose, 0.1), &DrawState::default(), transform, gl);
+        }
+
+        if let Some(ref path) = self.path {
+            path.draw(config, transform, gl);
+        }
+
+        let estimate_color = color::hex("55ddff");
+
+        // Draw the 2-sigma ellipse of the position
+        if let Some(cov) = self.position_covariance {
+            let (xx, xy, yy) = (cov[0][0], cov[0][1], cov[1][1]);
+            let mean = 0.5 * (xx + yy);
+            let deviation = (0.25 * (xx - yy).powi(2) + xy * xy).sqrt();
+            let major = 2.0 * (mean + deviation).max(0.0).sqrt();
+            let minor = 2.0 * (mean - deviation).max(0.0).sqrt();
+            let angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+
+            let point = |t: f64| {
+                let v = geometry::Vector::new(major * t.cos(), minor * t.sin()).rotate(angle);
+                config.pixel_coords(self.pose.position + v)
+            };
+
+            let line = Line::new(estimate_color, 1.0);
+            let segments = 48;
+            for i in 0..segments {
+                let t1 = 2.0 * ::math::PI * (i as f64) / (segments as f64);
+                let t2 = 2.0 * ::math::PI * ((i + 1) as f64) / (segments as f64);
+                let ((x1, y1), (x2, y2)) = (point(t1), point(t2));
+                line.draw([x1, y1, x2, y2], &DrawState::default(), transform, gl);
+            }
+        }
+
+        // Draw the estimated pose
+        let line = Line::new(estimate_color, 2.0);
+        line.draw(arrow(&self.pose, 0.3), &DrawState::default(), transform, gl);
+    }
+}
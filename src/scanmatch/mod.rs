@@ -0,0 +1,351 @@
+//! Scan matching with the iterative closest point (ICP) algorithm.
+
+use std::collections::HashMap;
+
+use geometry::{Pose, Vector};
+use math::{normalize_angle, Matrix3, Scalar};
+use pointcloud::PointCloud;
+
+/// Error metric that is minimized by ICP.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    /// Distance between corresponding points
+    PointToPoint,
+    /// Distance of a point to the line through the corresponding point
+    /// (along the estimated surface normal). Converges faster in structured
+    /// environments like walls.
+    PointToLine,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IcpConfig {
+    pub metric: Metric,
+    pub max_iterations: usize,
+    /// Points that are further away from their closest point in the
+    /// reference are treated as outliers
+    pub max_correspondence_distance: Scalar,
+    /// Stop once the change of the pose in one iteration is below this
+    /// threshold (in meters and radians)
+    pub convergence_threshold: Scalar,
+    /// Minimum number of correspondences for a valid match
+    pub min_correspondences: usize,
+    /// Radius of the neighborhood that is used to estimate surface normals
+    pub normal_radius: Scalar,
+}
+
+impl Default for IcpConfig {
+    fn default() -> IcpConfig {
+        IcpConfig {
+            metric: Metric::PointToLine,
+            max_iterations: 30,
+            max_correspondence_distance: 0.3,
+            convergence_threshold: 1e-5,
+            min_correspondences: 10,
+            normal_radius: 0.3,
+        }
+    }
+}
+
+/// Result of a successful scan match.
+#[derive(Debug, Clone)]
+pub struct MatchResult {
+    /// Pose of the scan relative to the reference
+    pub pose: Pose,
+    /// Fraction of the scan points that have a correspondence in the
+    /// reference (between 0 and 1)
+    pub fitness: Scalar,
+    /// Root mean square distance between corresponding points
+    pub rmse: Scalar,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Residual distance between corresponding points that is expected from the
+/// noise of the laser scanner. A match with this RMSE counts as half as
+/// reliable as a perfect one.
+const EXPECTED_RMSE: Scalar = 0.05;
+
+impl MatchResult {
+    /// Factor for the standard deviation of the pose of a match: one for a
+    /// perfect match, growing with the residual error and with the fraction
+    /// of points without a correspondence.
+    pub fn uncertainty_scale(&self) -> Scalar {
+        (1.0 + self.rmse / EXPECTED_RMSE) / self.fitness
+    }
+}
+
+/// Reference point cloud with a spatial index for nearest neighbor queries.
+pub struct Reference {
+    points: Vec<Vector>,
+    normals: Vec<Option<Vector>>,
+    cell_size: Scalar,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl Reference {
+    pub fn new(cloud: &PointCloud, config: &IcpConfig) -> Reference {
+        let points: Vec<Vector> = cloud.iter().map(|p| p.pos).collect();
+        let cell_size = config.max_correspondence_distance.max(config.normal_radius);
+
+        let mut reference = Reference {
+            points,
+            normals: vec![],
+            cell_size,
+            cells: HashMap::new(),
+        };
+
+        for (i, &p) in reference.points.iter().enumerate() {
+            let cell = reference.cell(p);
+            reference.cells.entry(cell).or_default().push(i);
+        }
+
+        reference.normals = reference
+            .points
+            .iter()
+            .map(|&p| reference.normal(p, config.normal_radius))
+            .collect();
+
+        reference
+    }
+
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    fn cell(&self, p: Vector) -> (i64, i64) {
+        (
+            (p.x / self.cell_size).floor() as i64,
+            (p.y / self.cell_size).floor() as i64,
+        )
+    }
+
+    /// Indices of all points within `radius` (at most the cell size) of `p`.
+    fn neighbors(&self, p: Vector, radius: Scalar) -> impl Iterator<Item = usize> + '_ {
+        let (cx, cy) = self.cell(p);
+
+        (cx - 1..=cx + 1)
+            .flat_map(move |x| (cy - 1..=cy + 1).map(move |y| (x, y)))
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flat_map(|indices| indices.iter().cloned())
+            .filter(move |&i| (self.points[i] - p).length() <= radius)
+    }
+
+    /// Closest reference point within `max_distance` of `p`.
+    pub fn closest(&self, p: Vector, max_distance: Scalar) -> Option<usize> {
+        self.neighbors(p, max_distance).min_by(|&a, &b| {
+            let da = (self.points[a] - p).length();
+            let db = (self.points[b] - p).length();
+            da.partial_cmp(&db).unwrap()
+        })
+    }
+
+    /// Estimate the surface normal at `p` from the principal axes of the
+    /// neighboring points.
+    fn normal(&self, p: Vector, radius: Scalar) -> Option<Vector> {
+        let neighbors: Vec<Vector> = self.neighbors(p, radius).map(|i| self.points[i]).collect();
+        if neighbors.len() < 3 {
+            return None;
+        }
+
+        let n = neighbors.len() as Scalar;
+        let mean = neighbors.iter().fold(Vector::new(0.0, 0.0), |s, &q| s + q) * (1.0 / n);
+
+        let (mut cxx, mut cxy, mut cyy) = (0.0, 0.0, 0.0);
+        for &q in &neighbors {
+            let d = q - mean;
+            cxx += d.x * d.x;
+            cxy += d.x * d.y;
+            cyy += d.y * d.y;
+        }
+
+        // Angle of the principal axis (direction of the surface)
+        let angle = 0.5 * (2.0 * cxy).atan2(cxx - cyy);
+        Some(Vector::new(-angle.sin(), angle.cos()))
+    }
+}
+
+/// Align `scan` (points in the scan frame) with `reference`, starting from
+/// the `initial` guess for the pose of the scan relative to the reference.
+/// Returns `None` if there are not enough correspondences.
+pub fn icp(
+    reference: &Reference,
+    scan: &PointCloud,
+    initial: &Pose,
+    config: &IcpConfig,
+) -> Option<MatchResult> {
+    let points: Vec<Vector> = scan.iter().map(|p| p.pos).collect();
+    if points.is_empty() {
+        return None;
+    }
+
+    let mut pose = initial.clone();
+    let mut converged = false;
+    let mut iterations = 0;
+
+    while iterations < config.max_iterations {
+        iterations += 1;
+
+        let pairs = correspondences(reference, &points, &pose, config);
+        if pairs.len() < config.min_correspondences {
+            return None;
+        }
+
+        let step = match config.metric {
+            Metric::PointToPoint => point_to_point_step(reference, &pairs),
+            Metric::PointToLine => point_to_line_step(reference, &pairs),
+        };
+        let step = step?;
+
+        pose = step.compose(&pose);
+
+        if step.position.length() < config.convergence_threshold
+            && step.heading.abs() < config.convergence_threshold
+        {
+            converged = true;
+            break;
+        }
+    }
+
+    let pairs = correspondences(reference, &points, &pose, config);
+    if pairs.len() < config.min_correspondences {
+        return None;
+    }
+
+    let squared_error: Scalar = pairs
+        .iter()
+        .map(|&(p, i)| (p - reference.points[i]).length().powi(2))
+        .sum();
+
+    Some(MatchResult {
+        pose,
+        fitness: pairs.len() as Scalar / points.len() as Scalar,
+        rmse: (squared_error / pairs.len() as Scalar).sqrt(),
+        iterations,
+        converged,
+    })
+}
+
+/// Scan points (transformed with the current pose), paired with the index of
+/// the closest reference point.
+fn correspondences(
+    reference: &Reference,
+    points: &[Vector],
+    pose: &Pose,
+    config: &IcpConfig,
+) -> Vec<(Vector, usize)> {
+    points
+        .iter()
+        .map(|&p| pose.transform(p))
+        .filter_map(|p| {
+            reference
+                .closest(p, config.max_correspondence_distance)
+                .map(|i| (p, i))
+        })
+        .collect()
+}
+
+/// Closed-form rigid transformation that minimizes the squared distances
+/// between corresponding points.
+fn point_to_point_step(reference: &Reference, pairs: &[(Vector, usize)]) -> Option<Pose> {
+    let n = pairs.len() as Scalar;
+    let zero = Vector::new(0.0, 0.0);
+
+    let mean_p = pairs.iter().fold(zero, |s, &(p, _)| s + p) * (1.0 / n);
+    let mean_q = pairs
+        .iter()
+        .fold(zero, |s, &(_, i)| s + reference.points[i])
+        * (1.0 / n);
+
+    let (mut dot, mut cross) = (0.0, 0.0);
+    for &(p, i) in pairs {
+        let p = p - mean_p;
+        let q = reference.points[i] - mean_q;
+        dot += p.dot(q);
+        cross += p.cross(q);
+    }
+
+    let heading = cross.atan2(dot);
+    let position = mean_q - mean_p.rotate(heading);
+
+    Some(Pose::new(position, heading))
+}
+
+/// Linearized least squares step that minimizes the distances of the points
+/// to the lines through their correspondences.
+fn point_to_line_step(reference: &Reference, pairs: &[(Vector, usize)]) -> Option<Pose> {
+    let mut hessian = Matrix3::zero();
+    let mut gradient = [0.0; 3];
+
+    for &(p, i) in pairs {
+        let n = match reference.normals[i] {
+            Some(n) => n,
+            None => continue,
+        };
+
+        // Residual and its derivative with respect to (x, y, heading)
+        let residual = n.dot(p - reference.points[i]);
+        let jacobian = [n.x, n.y, p.cross(n)];
+
+        hessian = hessian + Matrix3::outer(jacobian, jacobian);
+        for k in 0..3 {
+            gradient[k] -= jacobian[k] * residual;
+        }
+    }
+
+    let delta = hessian.solve(gradient)?;
+    Some(Pose::new(Vector::new(delta[0], delta[1]), delta[2]))
+}
+
+/// Estimates the motion of the robot by matching each scan against the
+/// previous one.
+pub struct ScanToScan {
+    pub config: IcpConfig,
+    /// Matches with a lower fitness are rejected
+    pub min_fitness: Scalar,
+    /// Matches that move the guess further than this (in meters) are
+    /// rejected. This catches matches that slide along featureless structures
+    /// like corridors. Unlimited by default.
+    pub max_translation_correction: Scalar,
+    /// Matches that rotate the guess further than this (in radians) are
+    /// rejected. Unlimited by default.
+    pub max_rotation_correction: Scalar,
+    /// Previous scan and the pose at which it was taken
+    previous: Option<(Reference, Pose)>,
+}
+
+impl ScanToScan {
+    pub fn new(config: IcpConfig, min_fitness: Scalar) -> ScanToScan {
+        ScanToScan {
+            config,
+            min_fitness,
+            max_translation_correction: Scalar::INFINITY,
+            max_rotation_correction: Scalar::INFINITY,
+            previous: None,
+        }
+    }
+
+    /// Estimate the pose at which `scan` (points in the scan frame) was
+    /// taken, by matching it against the previous scan. `guess` is the pose
+    /// according to other sources, e.g. odometry.
+    pub fn measure(&self, scan: &PointCloud, guess: &Pose) -> Option<(Pose, MatchResult)> {
+        let (ref reference, ref previous_pose) = *self.previous.as_ref()?;
+
+        let initial = previous_pose.inverse().compose(guess);
+        let result = icp(reference, scan, &initial, &self.config)?;
+
+        let correction = initial.inverse().compose(&result.pose);
+        if result.fitness >= self.min_fitness
+            && correction.position.length() <= self.max_translation_correction
+            && normalize_angle(correction.heading).abs() <= self.max_rotation_correction
+        {
+            Some((previous_pose.compose(&result.pose), result))
+        } else {
+            None
+        }
+    }
+
+    /// Use `scan`, taken at `pose`, as reference for the next scan.
+    pub fn set_reference(&mut self, scan: &PointCloud, pose: Pose) {
+        self.previous = Some((Reference::new(scan, &self.config), pose));
+    }
+}
@@ -0,0 +1,24 @@
+//! Types for dealing with bumper contact sensors
+
+/// State of the three bumper segments at the front of the robot.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BumperReading {
+    pub left: bool,
+    pub center: bool,
+    pub right: bool,
+}
+
+impl BumperReading {
+    pub fn any(&self) -> bool {
+        self.left || self.center || self.right
+    }
+
+    /// Segments that are pressed in either of the two readings.
+    pub fn combine(&self, other: &BumperReading) -> BumperReading {
+        BumperReading {
+            left: self.left || other.left,
+            center: self.center || other.center,
+            right: self.right || other.right,
+        }
+    }
+}
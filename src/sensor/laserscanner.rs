@@ -1,4 +1,5 @@
-/// Types for dealing with laser scanner measurements
+//! Types for dealing with laser scanner measurements
+
 use std::slice::Iter;
 
 use geometry::{Point, Pose, Vector};
@@ -10,11 +11,26 @@ use pointcloud::PointCloud;
 pub struct Measurement {
     pub angle: Angle,
     pub distance: Scalar,
+    /// Whether the beam was reflected by an object. For beams without a
+    /// return, `distance` is the maximum range of the scanner.
+    pub hit: bool,
 }
 
 impl Measurement {
     pub fn new(angle: Angle, distance: Scalar) -> Measurement {
-        Measurement { angle, distance }
+        Measurement {
+            angle,
+            distance,
+            hit: true,
+        }
+    }
+
+    pub fn no_return(angle: Angle, max_range: Scalar) -> Measurement {
+        Measurement {
+            angle,
+            distance: max_range,
+            hit: false,
+        }
     }
 
     pub fn to_vector(&self, pose: &Pose) -> Vector {
@@ -24,6 +40,7 @@ impl Measurement {
 }
 
 /// A full 360° scan from a laser scanner.
+#[derive(Clone)]
 pub struct Scan {
     measurements: Vec<Measurement>,
 }
@@ -43,10 +60,14 @@ impl Scan {
         self.measurements.iter()
     }
 
+    /// All measurements where the beam was actually reflected.
+    pub fn hits(&self) -> impl Iterator<Item = &Measurement> {
+        self.measurements.iter().filter(|m| m.hit)
+    }
+
     pub fn to_pointcloud(&self, pose: &Pose) -> PointCloud {
         PointCloud::new(
-            self.measurements
-                .iter()
+            self.hits()
                 .map(|m| m.to_vector(pose))
                 .map(Point::from_vector)
                 .collect(),
@@ -1 +1,29 @@
+pub mod bumper;
 pub mod laserscanner;
+pub mod odometry;
+
+use self::bumper::BumperReading;
+use self::laserscanner::Scan;
+use self::odometry::OdometryReading;
+use math::Scalar;
+
+/// Everything the robot perceives during one control cycle.
+pub struct SensorReadings {
+    /// Time at which the readings were taken, in seconds
+    pub time: Scalar,
+    /// Laser scan, if a new one has been completed since the last cycle
+    pub scan: Option<Scan>,
+    pub bumper: BumperReading,
+    pub odometry: OdometryReading,
+}
+
+impl Default for SensorReadings {
+    fn default() -> SensorReadings {
+        SensorReadings {
+            time: 0.0,
+            scan: None,
+            bumper: BumperReading::default(),
+            odometry: OdometryReading::default(),
+        }
+    }
+}
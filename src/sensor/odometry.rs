@@ -0,0 +1,45 @@
+//! Types for dealing with wheel odometry
+
+use math::{Scalar, PI};
+use motion::Motion;
+
+/// Nominal parameters of the differential drive.
+#[derive(Debug, Clone, Copy)]
+pub struct DriveGeometry {
+    pub wheel_radius: Scalar,
+    /// Distance between the two wheels
+    pub wheel_base: Scalar,
+    pub ticks_per_revolution: u32,
+}
+
+impl Default for DriveGeometry {
+    fn default() -> DriveGeometry {
+        DriveGeometry {
+            wheel_radius: 0.035,
+            wheel_base: 0.23,
+            ticks_per_revolution: 360,
+        }
+    }
+}
+
+impl DriveGeometry {
+    pub fn distance_per_tick(&self) -> Scalar {
+        2.0 * PI * self.wheel_radius / Scalar::from(self.ticks_per_revolution)
+    }
+
+    /// Dead-reckon the motion of the robot from the encoder ticks.
+    pub fn to_motion(&self, reading: &OdometryReading) -> Motion {
+        let left = reading.left_ticks as Scalar * self.distance_per_tick();
+        let right = reading.right_ticks as Scalar * self.distance_per_tick();
+        let rotation = (right - left) / self.wheel_base;
+
+        Motion::new(rotation / 2.0, (left + right) / 2.0, rotation / 2.0)
+    }
+}
+
+/// Encoder ticks counted on both wheels since the previous reading.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OdometryReading {
+    pub left_ticks: i64,
+    pub right_ticks: i64,
+}
@@ -0,0 +1,88 @@
+use math::Scalar;
+
+/// How simulated time relates to wall-clock time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockMode {
+    RealTime,
+    /// Simulated time runs faster than real time by the given factor
+    FastForward(Scalar),
+    Paused,
+}
+
+/// Converts elapsed wall-clock time into a number of fixed-size simulation
+/// steps, independent of the rate at which the clock is advanced.
+pub struct Clock {
+    /// Duration of one simulation step, in seconds
+    pub timestep: Scalar,
+    pub mode: ClockMode,
+    /// Upper limit for the number of steps per call to `advance`. This keeps
+    /// the application responsive if the simulation cannot keep up.
+    pub max_steps: u32,
+    accumulator: Scalar,
+}
+
+impl Clock {
+    pub fn new(timestep: Scalar) -> Clock {
+        Clock {
+            timestep,
+            mode: ClockMode::RealTime,
+            max_steps: 1000,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Let `dt` seconds of wall-clock time pass. Returns the number of
+    /// simulation steps that need to be performed.
+    pub fn advance(&mut self, dt: Scalar) -> u32 {
+        self.accumulator += match self.mode {
+            ClockMode::RealTime => dt,
+            ClockMode::FastForward(factor) => dt * factor,
+            ClockMode::Paused => 0.0,
+        };
+
+        let steps = (self.accumulator / self.timestep).floor();
+        self.accumulator -= steps * self.timestep;
+
+        if steps > Scalar::from(self.max_steps) {
+            // Drop the time that we cannot catch up with
+            self.accumulator = 0.0;
+            self.max_steps
+        } else {
+            steps as u32
+        }
+    }
+}
+
+/// Triggers a periodic task, such as a sensor update, at a fixed frequency in
+/// simulated time.
+pub struct Schedule {
+    /// Frequency in Hz
+    pub frequency: Scalar,
+    next: Scalar,
+}
+
+impl Schedule {
+    pub fn new(frequency: Scalar) -> Schedule {
+        Schedule {
+            frequency,
+            next: 0.0,
+        }
+    }
+
+    /// Time between two consecutive runs, in seconds.
+    pub fn period(&self) -> Scalar {
+        1.0 / self.frequency
+    }
+
+    /// Whether the task is due at the given (simulated) time. If so, the next
+    /// run is scheduled.
+    pub fn is_due(&mut self, time: Scalar) -> bool {
+        // Tolerate rounding errors in the accumulated simulation time
+        if time + 1e-9 >= self.next {
+            self.next += self.period();
+            true
+        } else {
+            false
+        }
+    }
+}
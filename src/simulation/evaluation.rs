@@ -0,0 +1,88 @@
+use std::fmt;
+
+use geometry::Pose;
+use math::{normalize_angle, Scalar};
+
+/// Compares the pose estimate of a controller with the ground truth. This is
+/// the only place where the true pose and the estimate meet.
+pub struct Evaluation {
+    /// True pose of the robot when the controller was started. This defines
+    /// the frame in which the controller reports its estimate.
+    origin: Pose,
+    num_samples: usize,
+    sum_squared_position_error: Scalar,
+    sum_squared_heading_error: Scalar,
+    max_position_error: Scalar,
+}
+
+impl Evaluation {
+    pub fn new(origin: Pose) -> Evaluation {
+        Evaluation {
+            origin,
+            num_samples: 0,
+            sum_squared_position_error: 0.0,
+            sum_squared_heading_error: 0.0,
+            max_position_error: 0.0,
+        }
+    }
+
+    pub fn origin(&self) -> &Pose {
+        &self.origin
+    }
+
+    /// Express a pose estimate of the controller in world coordinates.
+    pub fn to_world(&self, estimate: &Pose) -> Pose {
+        self.origin.compose(estimate)
+    }
+
+    pub fn record(&mut self, truth: &Pose, estimate: &Pose) {
+        let estimate = self.to_world(estimate);
+
+        let position_error = (estimate.position - truth.position).length();
+        let heading_error = normalize_angle(estimate.heading - truth.heading);
+
+        self.num_samples += 1;
+        self.sum_squared_position_error += position_error.powi(2);
+        self.sum_squared_heading_error += heading_error.powi(2);
+        self.max_position_error = self.max_position_error.max(position_error);
+    }
+
+    pub fn num_samples(&self) -> usize {
+        self.num_samples
+    }
+
+    /// Root mean square error of the estimated position.
+    pub fn position_rmse(&self) -> Scalar {
+        self.rms(self.sum_squared_position_error)
+    }
+
+    /// Root mean square error of the estimated heading.
+    pub fn heading_rmse(&self) -> Scalar {
+        self.rms(self.sum_squared_heading_error)
+    }
+
+    pub fn max_position_error(&self) -> Scalar {
+        self.max_position_error
+    }
+
+    fn rms(&self, sum_squared: Scalar) -> Scalar {
+        if self.num_samples == 0 {
+            0.0
+        } else {
+            (sum_squared / self.num_samples as Scalar).sqrt()
+        }
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "samples: {}, position RMSE: {:.3} m (max: {:.3} m), heading RMSE: {:.3} rad",
+            self.num_samples,
+            self.position_rmse(),
+            self.max_position_error,
+            self.heading_rmse()
+        )
+    }
+}
@@ -0,0 +1,42 @@
+use std::fs;
+use std::io::Read;
+
+use svg2polylines::{self, Polyline};
+
+use geometry::{Line, Pose, Vector};
+use math::Scalar;
+
+/// Size of one SVG pixel in meters.
+pub const METERS_PER_PIXEL: Scalar = 0.02;
+
+/// Read the static world (walls) from an SVG file.
+pub fn load_svg(path: &str) -> Result<Vec<Line>, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut s = String::new();
+    file.read_to_string(&mut s)
+        .map_err(|e| format!("{}: {}", path, e))?;
+
+    let polylines: Vec<Polyline> = svg2polylines::parse(&s)?;
+
+    let m_per_px = METERS_PER_PIXEL;
+    let mut objects = vec![];
+    for polyline in &polylines {
+        for pair in polyline.windows(2) {
+            objects.push(Line::new(
+                Vector::new(pair[0].x * m_per_px, -pair[0].y * m_per_px),
+                Vector::new(pair[1].x * m_per_px, -pair[1].y * m_per_px),
+            ))
+        }
+    }
+
+    Ok(objects)
+}
+
+/// Express the given lines (in world coordinates) in the local frame of `pose`.
+pub fn to_frame(lines: &[Line], pose: &Pose) -> Vec<Line> {
+    let inverse = pose.inverse();
+    lines
+        .iter()
+        .map(|l| Line::new(inverse.transform(l.start), inverse.transform(l.end)))
+        .collect()
+}
@@ -1,2 +1,117 @@
+pub mod clock;
+pub mod evaluation;
+pub mod map;
 pub mod robot;
 pub mod sensor;
+
+use std::mem;
+
+use controller::Controller;
+use geometry::Line;
+use math::Scalar;
+use motion::Velocity;
+use sensor::bumper::BumperReading;
+use sensor::laserscanner::Scan;
+use sensor::SensorReadings;
+
+use self::clock::Schedule;
+use self::evaluation::Evaluation;
+use self::robot::Robot;
+
+/// The complete simulated world: a static map, the robot and the controller
+/// that drives it.
+pub struct Simulation {
+    pub objects: Vec<Line>,
+    pub robot: Robot,
+    pub controller: Box<dyn Controller>,
+    /// Duration of one simulation step (physics update), in seconds
+    pub timestep: Scalar,
+    /// Rate at which the laser scanner completes a scan
+    pub scan_rate: Schedule,
+    /// Rate at which the controller is run
+    pub control_rate: Schedule,
+    /// Most recent laser scan
+    pub last_scan: Scan,
+    /// Sensor readings of the last control cycle
+    pub readings: SensorReadings,
+    pub evaluation: Evaluation,
+    /// Manual velocity command that overrides the controller (teleoperation)
+    pub teleop: Option<Velocity>,
+    new_scan: Option<Scan>,
+    bumper: BumperReading,
+    ticks: u64,
+}
+
+impl Simulation {
+    pub fn new(objects: Vec<Line>, robot: Robot, controller: Box<dyn Controller>) -> Simulation {
+        let evaluation = Evaluation::new(robot.pose.clone());
+
+        Simulation {
+            objects,
+            robot,
+            controller,
+            timestep: 0.01,
+            scan_rate: Schedule::new(10.0),
+            control_rate: Schedule::new(20.0),
+            last_scan: Scan::empty(),
+            readings: SensorReadings::default(),
+            evaluation,
+            teleop: None,
+            new_scan: None,
+            bumper: BumperReading::default(),
+            ticks: 0,
+        }
+    }
+
+    /// Advance the simulation by one timestep. Sensors and the controller are
+    /// updated whenever they are due.
+    pub fn step(&mut self) {
+        let time = self.time();
+
+        if self.scan_rate.is_due(time) {
+            let scan = self
+                .robot
+                .laser_scanner
+                .scan(&self.robot.pose, &self.objects);
+
+            self.last_scan = scan.clone();
+            self.new_scan = Some(scan);
+        }
+
+        if self.control_rate.is_due(time) {
+            self.readings = SensorReadings {
+                time,
+                scan: self.new_scan.take(),
+                bumper: mem::take(&mut self.bumper),
+                odometry: self.robot.wheel_encoders.read(),
+            };
+
+            // The controller keeps running during teleoperation, so that its
+            // pose estimate and map stay up to date
+            let command = self.controller.cycle(&self.readings);
+            let command = self.teleop.unwrap_or(command);
+            self.robot.command(&command, self.control_rate.period());
+
+            // Compare the estimate with the ground truth
+            self.evaluation
+                .record(&self.robot.pose, &self.controller.pose_estimate());
+        }
+
+        self.robot.step(self.timestep, &self.objects);
+
+        // Bumper contacts are latched until the next control cycle
+        let bumper = self.robot.bumper.read(self.robot.collision.as_ref());
+        self.bumper = self.bumper.combine(&bumper);
+
+        self.ticks += 1;
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Simulated time in seconds.
+    pub fn time(&self) -> Scalar {
+        self.ticks as Scalar * self.timestep
+    }
+}
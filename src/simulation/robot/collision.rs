@@ -0,0 +1,212 @@
+use geometry::{Line, Vector};
+use math::{normalize_angle, Angle, Scalar};
+
+/// Safety margin that is kept between the robot body and an obstacle after a
+/// collision, to avoid numerical problems in the next step.
+const CONTACT_MARGIN: Scalar = 1e-4;
+
+/// What happens to the robot when it runs into an obstacle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionResponse {
+    /// The robot stops at the point of contact.
+    Stop,
+    /// The remaining motion is projected onto the obstacle surface.
+    Slide,
+}
+
+/// A contact between the robot body and an obstacle.
+#[derive(Debug, Clone, Copy)]
+pub struct Collision {
+    /// Point of contact in world coordinates
+    pub point: Vector,
+    /// Direction of the contact point, relative to the heading of the robot
+    pub bearing: Angle,
+}
+
+/// Move a disc of the given radius from `position` by `displacement`, stopping
+/// or sliding at the first obstacle in its way. Returns the new position and
+/// the point of contact, if any.
+pub fn sweep(
+    position: Vector,
+    displacement: Vector,
+    radius: Scalar,
+    obstacles: &[Line],
+    response: CollisionResponse,
+) -> (Vector, Option<Vector>) {
+    match first_contact(position, displacement, radius, obstacles) {
+        None => (position + displacement, None),
+        Some((t, line)) => {
+            let length = displacement.length();
+            let t_safe = (t - CONTACT_MARGIN / length).max(0.0);
+            let center = position + displacement * t;
+            let contact = line.closest_point(center);
+
+            let mut new_position = position + displacement * t_safe;
+
+            let normal = contact_normal(center, contact, line, displacement);
+            if let (CollisionResponse::Slide, Some(normal)) = (response, normal) {
+                let remaining = displacement * (1.0 - t);
+                let tangential = remaining - normal * remaining.dot(normal);
+
+                new_position = match first_contact(new_position, tangential, radius, obstacles) {
+                    None => new_position + tangential,
+                    Some((t2, _)) => {
+                        let t2_safe = (t2 - CONTACT_MARGIN / tangential.length()).max(0.0);
+                        new_position + tangential * t2_safe
+                    }
+                };
+            }
+
+            (new_position, Some(contact))
+        }
+    }
+}
+
+/// Unit normal of the obstacle surface at the point of contact, pointing
+/// towards the center of the disc. If the center lies on the obstacle itself,
+/// the perpendicular of the obstacle against the displacement is used.
+/// Returns `None` for an obstacle without extent.
+fn contact_normal(
+    center: Vector,
+    contact: Vector,
+    line: &Line,
+    displacement: Vector,
+) -> Option<Vector> {
+    let mut normal = center - contact;
+    if normal.length() == 0.0 {
+        let direction = line.end - line.start;
+        normal = Vector::new(-direction.y, direction.x);
+        if normal.dot(displacement) > 0.0 {
+            normal = normal * -1.0;
+        }
+    }
+
+    let length = normal.length();
+    if length > 0.0 {
+        Some(normal * (1.0 / length))
+    } else {
+        None
+    }
+}
+
+/// The bearing of a world point, as seen from a robot at `position` with the
+/// given heading.
+pub fn bearing(position: Vector, heading: Angle, point: Vector) -> Angle {
+    normalize_angle((point - position).angle() - heading)
+}
+
+/// Find the fraction of `displacement` after which a disc starting at
+/// `position` first touches one of the obstacles.
+fn first_contact(
+    position: Vector,
+    displacement: Vector,
+    radius: Scalar,
+    obstacles: &[Line],
+) -> Option<(Scalar, &Line)> {
+    if displacement.length() == 0.0 {
+        return None;
+    }
+
+    obstacles
+        .iter()
+        .filter_map(|line| time_of_impact(position, displacement, radius, line).map(|t| (t, line)))
+        .fold(None, |best, (t, line)| match best {
+            Some((t_best, _)) if t_best <= t => best,
+            _ => Some((t, line)),
+        })
+}
+
+/// Time of impact (as a fraction of `d`) of a disc with center `p` and radius
+/// `r`, moving along `d`, with a line segment. This is a raycast against the
+/// "capsule" around the segment.
+fn time_of_impact(p: Vector, d: Vector, r: Scalar, line: &Line) -> Option<Scalar> {
+    // Already in contact: only allow motions that increase the distance
+    let closest = line.closest_point(p);
+    if (p - closest).length() < r {
+        return if d.dot(p - closest) < 0.0 {
+            Some(0.0)
+        } else {
+            None
+        };
+    }
+
+    let in_range = |t: Scalar| (0.0..=1.0).contains(&t);
+    let mut candidates = vec![];
+
+    // The two flat sides of the capsule
+    let s = line.end - line.start;
+    let len2 = s.dot(s);
+    if len2 > 0.0 {
+        let n = Vector::new(-s.y, s.x) * (1.0 / len2.sqrt());
+        let s0 = (p - line.start).dot(n);
+        let ds = d.dot(n);
+        let side = s0.signum();
+
+        if ds * side < 0.0 {
+            let t = (side * r - s0) / ds;
+            let u = (p + d * t - line.start).dot(s) / len2;
+            if in_range(t) && in_range(u) {
+                candidates.push(t);
+            }
+        }
+    }
+
+    // The round caps at both ends
+    for &e in &[line.start, line.end] {
+        let f = p - e;
+        let a = d.dot(d);
+        let b = 2.0 * f.dot(d);
+        let c = f.dot(f) - r * r;
+        let disc = b * b - 4.0 * a * c;
+
+        if disc >= 0.0 {
+            let t = (-b - disc.sqrt()) / (2.0 * a);
+            if in_range(t) {
+                candidates.push(t);
+            }
+        }
+    }
+
+    candidates.into_iter().fold(None, |best, t| match best {
+        Some(b) if b <= t => best,
+        _ => Some(t),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slide_along_wall() {
+        let wall = [Line::new(Vector::new(1.0, -5.0), Vector::new(1.0, 5.0))];
+        let (position, contact) = sweep(
+            Vector::new(0.0, 0.0),
+            Vector::new(2.0, 1.0),
+            0.5,
+            &wall,
+            CollisionResponse::Slide,
+        );
+
+        assert!((position.x - 0.5).abs() < 1e-3);
+        assert!((position.y - 1.0).abs() < 1e-3);
+        assert!((contact.unwrap().x - 1.0).abs() < 1e-9);
+    }
+
+    /// A disc without extent touches the wall with its center.
+    #[test]
+    fn slide_with_center_on_wall() {
+        let wall = [Line::new(Vector::new(1.0, -5.0), Vector::new(1.0, 5.0))];
+        let (position, _) = sweep(
+            Vector::new(0.0, 0.0),
+            Vector::new(2.0, 1.0),
+            0.0,
+            &wall,
+            CollisionResponse::Slide,
+        );
+
+        assert!(position.x <= 1.0);
+        assert!((position.x - 1.0).abs() < 1e-3);
+        assert!((position.y - 1.0).abs() < 1e-3);
+    }
+}
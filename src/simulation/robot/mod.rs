@@ -1,7 +1,117 @@
-use geometry::Pose;
+pub mod collision;
+
+use rand::{SeedableRng, StdRng};
+
+use geometry::{Line, Pose};
+use math::{Angle, Scalar};
+use motion::{Motion, MotionNoise, Velocity};
+use sensor::odometry::DriveGeometry;
+use simulation::sensor::bumper::Bumper;
 use simulation::sensor::laserscanner::LaserScanner;
+use simulation::sensor::odometry::WheelEncoders;
+
+use self::collision::{Collision, CollisionResponse};
 
 pub struct Robot {
     pub pose: Pose,
+    /// Radius of the (disc-shaped) robot body
+    pub radius: Scalar,
     pub laser_scanner: LaserScanner,
+    pub bumper: Bumper,
+    pub wheel_encoders: WheelEncoders,
+    pub motion_noise: MotionNoise,
+    pub collision_response: CollisionResponse,
+    /// Contact with an obstacle during the last motion
+    pub collision: Option<Collision>,
+    /// Velocity that the robot is actually driving with
+    velocity: Velocity,
+    rng: StdRng,
+}
+
+impl Robot {
+    pub fn new(
+        pose: Pose,
+        laser_scanner: LaserScanner,
+        motion_noise: MotionNoise,
+        seed: u64,
+    ) -> Robot {
+        Robot {
+            pose,
+            radius: 0.17,
+            laser_scanner,
+            bumper: Bumper::default(),
+            wheel_encoders: WheelEncoders::new(DriveGeometry::default(), seed.wrapping_add(1)),
+            motion_noise,
+            collision_response: CollisionResponse::Slide,
+            collision: None,
+            velocity: Velocity::stop(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Turn the robot in place (counterclockwise for positive angles).
+    pub fn rotate_by(&mut self, angle: Angle, obstacles: &[Line]) -> Motion {
+        self.execute(&Motion::rotation(angle), obstacles)
+    }
+
+    /// Drive straight along the current heading.
+    pub fn drive_forward(&mut self, distance: Scalar, obstacles: &[Line]) -> Motion {
+        self.execute(&Motion::translation(distance), obstacles)
+    }
+
+    /// Perform the commanded motion with simulated imprecision. The robot
+    /// cannot move through obstacles. Returns the motion that the wheels have
+    /// actually carried out, regardless of whether the robot was blocked.
+    pub fn execute(&mut self, command: &Motion, obstacles: &[Line]) -> Motion {
+        let actual = self.motion_noise.sample(command, &mut self.rng);
+        self.perform(&actual, obstacles);
+
+        actual
+    }
+
+    /// Drive with the commanded velocity for the given duration (in seconds).
+    /// The motion noise is applied to the motion over the whole duration, the
+    /// robot then keeps a constant (noisy) velocity until the next command.
+    pub fn command(&mut self, command: &Velocity, duration: Scalar) {
+        let actual = self
+            .motion_noise
+            .sample(&command.to_motion(duration), &mut self.rng);
+
+        self.velocity = Velocity::from_motion(&actual, duration);
+    }
+
+    /// Advance the robot by one timestep of the simulation, with its current
+    /// velocity.
+    pub fn step(&mut self, dt: Scalar, obstacles: &[Line]) {
+        let motion = self.velocity.to_motion(dt);
+        self.perform(&motion, obstacles);
+    }
+
+    /// Move the robot to the given pose instantly ("kidnapping"). The wheel
+    /// encoders do not notice.
+    pub fn teleport(&mut self, pose: Pose) {
+        self.pose = pose;
+        self.velocity = Velocity::stop();
+        self.collision = None;
+    }
+
+    fn perform(&mut self, actual: &Motion, obstacles: &[Line]) {
+        let target = actual.apply(&self.pose);
+
+        let (position, contact) = collision::sweep(
+            self.pose.position,
+            target.position - self.pose.position,
+            self.radius,
+            obstacles,
+            self.collision_response,
+        );
+
+        self.wheel_encoders.record(actual);
+
+        self.pose = Pose::new(position, target.heading);
+        self.collision = contact.map(|point| Collision {
+            point,
+            bearing: collision::bearing(position, target.heading, point),
+        });
+    }
 }
@@ -0,0 +1,37 @@
+use math::{Angle, PI};
+use sensor::bumper::BumperReading;
+use simulation::robot::collision::Collision;
+
+/// A bumper covering an arc of the robot outline, centered at the front. The
+/// arc is split into three segments of equal size.
+pub struct Bumper {
+    pub arc: Angle,
+}
+
+impl Default for Bumper {
+    fn default() -> Bumper {
+        Bumper { arc: PI }
+    }
+}
+
+impl Bumper {
+    pub fn read(&self, collision: Option<&Collision>) -> BumperReading {
+        let mut reading = BumperReading::default();
+
+        if let Some(c) = collision {
+            let segment = self.arc / 3.0;
+
+            if c.bearing.abs() <= self.arc / 2.0 {
+                if c.bearing > segment / 2.0 {
+                    reading.left = true;
+                } else if c.bearing < -segment / 2.0 {
+                    reading.right = true;
+                } else {
+                    reading.center = true;
+                }
+            }
+        }
+
+        reading
+    }
+}
@@ -1,18 +1,38 @@
 use std::cmp::Ordering;
 
+use rand::{Rng, SeedableRng, StdRng};
+
 use geometry::{Line, Point, Pose, Ray, Target};
-use math::{Angle, Scalar, PI};
+use math::{sample_normal, Angle, Scalar, PI};
 use sensor::laserscanner::{Measurement, Scan};
 
 pub struct LaserScanner {
     pub num_columns: u32,
-    // max_range: Scalar,
-    // range_noise: Scalar,
-    // angle_noise: Angle
+    /// Objects further away than this do not produce a return
+    pub max_range: Scalar,
+    /// Standard deviation of the measured distance
+    pub range_noise: Scalar,
+    /// Standard deviation of the actual beam direction
+    pub angle_noise: Angle,
+    /// Probability that a single beam does not produce a measurement at all
+    /// (clamped to the range from 0 to 1)
+    pub dropout_probability: f64,
+    rng: StdRng,
 }
 
 impl LaserScanner {
-    pub fn scan(&self, pose: &Pose, targets: &[Line]) -> Scan {
+    pub fn new(num_columns: u32, seed: u64) -> LaserScanner {
+        LaserScanner {
+            num_columns,
+            max_range: 8.0,
+            range_noise: 0.01,
+            angle_noise: 0.002,
+            dropout_probability: 0.01,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn scan(&mut self, pose: &Pose, targets: &[Line]) -> Scan {
         let mut scan = Scan::empty();
 
         // Comparison function to find the closest point
@@ -20,8 +40,13 @@ impl LaserScanner {
 
         // Raycasting
         for col in 0..self.num_columns {
+            if self.rng.gen_bool(self.dropout_probability.clamp(0.0, 1.0)) {
+                continue;
+            }
+
             let col_angle = self.column_to_angle(col);
-            let ray = Ray::from_angle(pose.position, pose.heading + col_angle);
+            let beam_angle = col_angle + sample_normal(&mut self.rng, self.angle_noise);
+            let ray = Ray::from_angle(pose.position, pose.heading + beam_angle);
 
             let mut points = vec![];
             for target in targets.iter() {
@@ -36,9 +61,15 @@ impl LaserScanner {
                     .unwrap_or(Ordering::Equal)
             });
 
-            if let Some(p) = closest {
-                scan.add(Measurement::new(col_angle, distance(p)));
-            }
+            let measurement = match closest {
+                Some(p) if distance(p) <= self.max_range => {
+                    let noisy = distance(p) + sample_normal(&mut self.rng, self.range_noise);
+                    Measurement::new(col_angle, noisy.max(0.0).min(self.max_range))
+                }
+                _ => Measurement::no_return(col_angle, self.max_range),
+            };
+
+            scan.add(measurement);
         }
 
         scan
@@ -1 +1,3 @@
+pub mod bumper;
 pub mod laserscanner;
+pub mod odometry;
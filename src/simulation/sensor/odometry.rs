@@ -0,0 +1,70 @@
+use rand::{SeedableRng, StdRng};
+
+use math::{sample_normal, Scalar, PI};
+use motion::Motion;
+use sensor::odometry::{DriveGeometry, OdometryReading};
+
+/// Simulated wheel encoders of a differential drive.
+pub struct WheelEncoders {
+    /// Geometry of the drive, as specified
+    pub geometry: DriveGeometry,
+    /// Relative deviation of the actual left wheel radius from the nominal one
+    pub left_radius_error: Scalar,
+    /// Relative deviation of the actual right wheel radius from the nominal one
+    pub right_radius_error: Scalar,
+    /// Standard deviation of the relative wheel slip
+    pub slip_noise: Scalar,
+    left_ticks: Scalar,
+    right_ticks: Scalar,
+    rng: StdRng,
+}
+
+impl WheelEncoders {
+    pub fn new(geometry: DriveGeometry, seed: u64) -> WheelEncoders {
+        WheelEncoders {
+            geometry,
+            left_radius_error: 0.005,
+            right_radius_error: -0.003,
+            slip_noise: 0.02,
+            left_ticks: 0.0,
+            right_ticks: 0.0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Turn the wheels according to a motion that has been carried out.
+    pub fn record(&mut self, motion: &Motion) {
+        // Turning in place moves the wheels in opposite directions
+        let rotation = motion.rotation1 + motion.rotation2;
+        let offset = rotation * self.geometry.wheel_base / 2.0;
+
+        let left = self.slip(motion.translation - offset);
+        let right = self.slip(motion.translation + offset);
+
+        self.left_ticks += self.distance_to_ticks(left, self.left_radius_error);
+        self.right_ticks += self.distance_to_ticks(right, self.right_radius_error);
+    }
+
+    /// Read out the full ticks that have been counted since the last reading.
+    pub fn read(&mut self) -> OdometryReading {
+        let left = self.left_ticks.trunc();
+        let right = self.right_ticks.trunc();
+
+        self.left_ticks -= left;
+        self.right_ticks -= right;
+
+        OdometryReading {
+            left_ticks: left as i64,
+            right_ticks: right as i64,
+        }
+    }
+
+    fn slip(&mut self, distance: Scalar) -> Scalar {
+        distance * (1.0 + sample_normal(&mut self.rng, self.slip_noise))
+    }
+
+    fn distance_to_ticks(&self, distance: Scalar, radius_error: Scalar) -> Scalar {
+        let radius = self.geometry.wheel_radius * (1.0 + radius_error);
+        distance / (2.0 * PI * radius) * Scalar::from(self.geometry.ticks_per_revolution)
+    }
+}